@@ -1,11 +1,12 @@
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{to_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult};
-use cw2::set_contract_version;
+use cw2::{get_contract_version, set_contract_version};
 
 use crate::error::ContractError;
 use crate::execute as ExecHandler;
-use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
+use crate::migrations::{check_migrated, migrate_state, parse_version, DEFAULT_MIGRATION_LIMIT};
+use crate::msg::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg, Region};
 use crate::query as QueryHandler;
 
 // version info for migration info
@@ -18,7 +19,7 @@ pub fn instantiate(
     _env: Env,
     info: MessageInfo,
     msg: InstantiateMsg,
-) -> StdResult<Response> {
+) -> Result<Response, ContractError> {
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
     ExecHandler::instantiate(deps, info, msg)
 }
@@ -30,18 +31,159 @@ pub fn execute(
     info: MessageInfo,
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
+    check_migrated(deps.storage, CONTRACT_VERSION)?;
     match msg {
-        ExecuteMsg::UpdateConfig { owner } => ExecHandler::execute_update_config(deps, info, owner),
+        ExecuteMsg::UpdateConfig {
+            owner,
+            mint_fee,
+            edit_fee,
+            edit_cooldown,
+            pricing,
+            platform_fee,
+            royalty,
+        } => ExecHandler::execute_update_config(
+            deps,
+            info,
+            owner,
+            mint_fee,
+            edit_fee,
+            edit_cooldown,
+            pricing,
+            platform_fee,
+            royalty,
+        ),
+        ExecuteMsg::SetPriceZones { zones } => {
+            ExecHandler::execute_set_price_zones(deps, info, zones)
+        }
+        ExecuteMsg::CreateAuction {
+            tiles,
+            start_price,
+            end_price,
+            start,
+            end,
+        } => ExecHandler::execute_create_auction(
+            deps,
+            info,
+            env,
+            tiles,
+            start_price,
+            end_price,
+            start,
+            end,
+        ),
+        ExecuteMsg::BidAuction {
+            auction_id,
+            token_id,
+        } => ExecHandler::execute_bid_auction(deps, info, env, auction_id, token_id),
+        ExecuteMsg::CreateEnglishAuction {
+            token_id,
+            min_bid,
+            expires,
+        } => {
+            ExecHandler::execute_create_english_auction(deps, info, env, token_id, min_bid, expires)
+        }
+        ExecuteMsg::PlaceBid { token_id } => {
+            ExecHandler::execute_place_bid(deps, info, env, token_id)
+        }
+        ExecuteMsg::SettleAuction { token_id } => {
+            ExecHandler::execute_settle_auction(deps, env, token_id)
+        }
+        ExecuteMsg::ListTile { token_id, price } => {
+            ExecHandler::execute_list_tile(deps, info, token_id, price)
+        }
+        ExecuteMsg::BuyTile { token_id } => ExecHandler::execute_buy_tile(deps, info, token_id),
+        ExecuteMsg::CancelListing { token_id } => {
+            ExecHandler::execute_cancel_listing(deps, info, token_id)
+        }
+        ExecuteMsg::OfferLease {
+            token_id,
+            price,
+            duration,
+        } => ExecHandler::execute_offer_lease(deps, info, env, token_id, price, duration),
+        ExecuteMsg::AcceptLease { token_id } => {
+            ExecHandler::execute_accept_lease(deps, info, env, token_id)
+        }
+        ExecuteMsg::CancelLeaseOffer { token_id } => {
+            ExecHandler::execute_cancel_lease_offer(deps, info, token_id)
+        }
+        ExecuteMsg::ProposeOwner { owner } => ExecHandler::execute_propose_owner(deps, info, owner),
+        ExecuteMsg::AcceptOwnership {} => ExecHandler::execute_accept_ownership(deps, info),
+        ExecuteMsg::CancelOwnershipProposal {} => {
+            ExecHandler::execute_cancel_ownership_proposal(deps, info)
+        }
+        ExecuteMsg::UpdatePalette { palette } => {
+            ExecHandler::execute_update_palette(deps, info, palette)
+        }
         ExecuteMsg::Mint {
             token_id,
             color_map,
+            pixels,
             url,
-        } => ExecHandler::execute_mint(deps, info, env, token_id, color_map, url),
+        } => ExecHandler::execute_mint(deps, info, env, token_id, color_map, pixels, url),
+        ExecuteMsg::BatchMint { tiles } => ExecHandler::execute_batch_mint(deps, info, env, tiles),
+        ExecuteMsg::MintRegion {
+            x,
+            y,
+            width,
+            height,
+            color_maps,
+        } => ExecHandler::execute_mint_region(
+            deps,
+            info,
+            env,
+            Region {
+                x,
+                y,
+                width,
+                height,
+            },
+            color_maps,
+        ),
         ExecuteMsg::ChangePixelData {
             token_id,
             color_map,
+            pixels,
             url,
-        } => ExecHandler::execute_change_pixel_data(deps, info, env, token_id, color_map, url),
+        } => ExecHandler::execute_change_pixel_data(
+            deps, info, env, token_id, color_map, pixels, url,
+        ),
+        ExecuteMsg::BatchChangePixelData { changes } => {
+            ExecHandler::execute_batch_change_pixel_data(deps, info, env, changes)
+        }
+        ExecuteMsg::UndoPixelChange { token_id } => {
+            ExecHandler::execute_undo_pixel_change(deps, info, env, token_id)
+        }
+        ExecuteMsg::SetPixels { token_id, pixels } => {
+            ExecHandler::execute_set_pixels(deps, info, env, token_id, pixels)
+        }
+        ExecuteMsg::ApproveEditor {
+            token_id,
+            editor,
+            expires,
+        } => ExecHandler::execute_approve_editor(deps, info, env, token_id, editor, expires),
+        ExecuteMsg::RevokeEditor { token_id, editor } => {
+            ExecHandler::execute_revoke_editor(deps, info, env, token_id, editor)
+        }
+        ExecuteMsg::WithdrawFees { recipient, amount } => {
+            ExecHandler::execute_withdraw_fees(deps, info, env, recipient, amount)
+        }
+        ExecuteMsg::TransferNft {
+            recipient,
+            token_id,
+        } => ExecHandler::execute_transfer_nft(deps, info, env, recipient, token_id),
+        ExecuteMsg::SendNft {
+            contract,
+            token_id,
+            msg,
+        } => ExecHandler::execute_send_nft(deps, info, env, contract, token_id, msg),
+        ExecuteMsg::Approve {
+            spender,
+            token_id,
+            expires,
+        } => ExecHandler::execute_approve(deps, info, env, spender, token_id, expires),
+        ExecuteMsg::Revoke { spender, token_id } => {
+            ExecHandler::execute_revoke(deps, info, env, spender, token_id)
+        }
         _ => ExecHandler::cw721_base_execute(deps, env, info, msg),
     }
 }
@@ -53,6 +195,132 @@ pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
         QueryMsg::PixelInfo { token_id } => {
             to_binary(&QueryHandler::query_pixel_nft_info(deps, token_id)?)
         }
+        QueryMsg::PixelInfos { start_after, limit } => {
+            to_binary(&QueryHandler::query_pixel_infos(deps, start_after, limit)?)
+        }
+        QueryMsg::PixelInfosByOwner {
+            owner,
+            start_after,
+            limit,
+        } => to_binary(&QueryHandler::query_pixel_infos_by_owner(
+            deps,
+            owner,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::Editors { token_id } => {
+            to_binary(&QueryHandler::query_editors(deps, env, token_id)?)
+        }
+        QueryMsg::Canvas {
+            x,
+            y,
+            width,
+            height,
+        } => to_binary(&QueryHandler::query_canvas(
+            deps,
+            Region {
+                x,
+                y,
+                width,
+                height,
+            },
+        )?),
+        QueryMsg::MintPrice { token_id } => {
+            to_binary(&QueryHandler::query_mint_price(deps, token_id)?)
+        }
+        QueryMsg::Auctions { start_after, limit } => to_binary(&QueryHandler::query_auctions(
+            deps,
+            env,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::EnglishAuction { token_id } => {
+            to_binary(&QueryHandler::query_english_auction(deps, token_id)?)
+        }
+        QueryMsg::Listings { start_after, limit } => {
+            to_binary(&QueryHandler::query_listings(deps, start_after, limit)?)
+        }
+        QueryMsg::Lease { token_id } => to_binary(&QueryHandler::query_lease(deps, env, token_id)?),
+        QueryMsg::PriceZones {} => to_binary(&QueryHandler::query_price_zones(deps)?),
+        QueryMsg::PixelHistory { token_id, limit } => {
+            to_binary(&QueryHandler::query_pixel_history(deps, token_id, limit)?)
+        }
+        QueryMsg::ColorMap { token_id } => {
+            to_binary(&QueryHandler::query_color_map(deps, token_id)?)
+        }
+        QueryMsg::Balance {} => to_binary(&QueryHandler::query_balance(deps, env)?),
+        QueryMsg::RoyaltyInfo {
+            token_id,
+            sale_price,
+        } => to_binary(&QueryHandler::query_royalty_info(
+            deps, token_id, sale_price,
+        )?),
+        QueryMsg::CheckRoyalties {} => to_binary(&QueryHandler::query_check_royalties()),
+        QueryMsg::OwnerOf {
+            token_id,
+            include_expired,
+        } => to_binary(&QueryHandler::query_owner_of(
+            deps,
+            env,
+            token_id,
+            include_expired.unwrap_or(false),
+        )?),
+        QueryMsg::NftInfo { token_id } => to_binary(&QueryHandler::query_nft_info(deps, token_id)?),
+        QueryMsg::AllNftInfo {
+            token_id,
+            include_expired,
+        } => to_binary(&QueryHandler::query_all_nft_info(
+            deps,
+            env,
+            token_id,
+            include_expired.unwrap_or(false),
+        )?),
+        QueryMsg::Tokens {
+            owner,
+            start_after,
+            limit,
+        } => to_binary(&QueryHandler::query_tokens(
+            deps,
+            owner,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::AllTokens { start_after, limit } => {
+            to_binary(&QueryHandler::query_all_tokens(deps, start_after, limit)?)
+        }
         _ => QueryHandler::cw721_base_query(deps, env, msg),
     }
 }
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(deps: DepsMut, _env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
+    let stored = get_contract_version(deps.storage)?;
+    if stored.contract != CONTRACT_NAME {
+        return Err(ContractError::InvalidMigrationContract {
+            contract: stored.contract,
+        });
+    }
+    if parse_version(&stored.version)? > parse_version(CONTRACT_VERSION)? {
+        return Err(ContractError::CannotDowngrade {
+            from: stored.version,
+            to: CONTRACT_VERSION.to_string(),
+        });
+    }
+
+    let limit = msg.limit.unwrap_or(DEFAULT_MIGRATION_LIMIT) as usize;
+    let progress = migrate_state(deps.storage, &stored.version, CONTRACT_VERSION, limit)?;
+    // Until all migrations are complete, record the version whose layout the state now has.
+    let version: &str = match (progress.complete, progress.applied.last()) {
+        (true, _) => CONTRACT_VERSION,
+        (false, Some(applied)) => applied,
+        (false, None) => &stored.version,
+    };
+    set_contract_version(deps.storage, CONTRACT_NAME, version)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "migrate")
+        .add_attribute("from_version", stored.version.clone())
+        .add_attribute("to_version", version)
+        .add_attribute("migrations", progress.applied.join(","))
+        .add_attribute("complete", progress.complete.to_string()))
+}
@@ -1,4 +1,5 @@
-use cosmwasm_std::StdError;
+use cosmwasm_std::{Coin, StdError};
+use cw721::Expiration;
 use cw721_base::ContractError as CW721ContractError;
 use thiserror::Error;
 
@@ -24,6 +25,99 @@ pub enum ContractError {
 
     #[error("Insufficient funds")]
     InsufficientFunds {},
+
+    #[error("No tiles given")]
+    NoTiles {},
+
+    #[error("Claimed tiles: {token_ids:?}")]
+    RegionClaimed { token_ids: Vec<u16> },
+
+    #[error("Expected one color map per tile")]
+    InvalidColorMaps {},
+
+    #[error("Color map does not match the tile size")]
+    InvalidColorMapSize {},
+
+    #[error("Pixel data may be given as a color map or as pixels, not both")]
+    ConflictingPixelData {},
+
+    #[error("Palette must hold between 1 and 256 colors")]
+    InvalidPalette {},
+
+    #[error("Color is not in the palette")]
+    ColorNotInPalette {},
+
+    #[error("No pixels given")]
+    NoPixels {},
+
+    #[error("Pixel ({x}, {y}) is outside the tile")]
+    PixelOutOfRange { x: u8, y: u8 },
+
+    #[error("Tile has no earlier pixel data")]
+    NoHistory {},
+
+    #[error("Tile cannot be edited before {until}")]
+    CooldownActive { until: Expiration },
+
+    #[error("Price zones must lie on the wall and be priced in the mint fee denom")]
+    InvalidPriceZone {},
+
+    #[error("Invalid auction")]
+    InvalidAuction {},
+
+    #[error("Auction is not active")]
+    AuctionNotActive {},
+
+    #[error("Tile is not sold in this auction")]
+    NotInAuction {},
+
+    #[error("Bid must be at least {min_bid}")]
+    BidTooLow { min_bid: Coin },
+
+    #[error("Auction has not ended yet")]
+    AuctionNotEnded {},
+
+    #[error("Tile is not for sale")]
+    NotListed {},
+
+    #[error("Platform fee must be at most 1")]
+    InvalidPlatformFee {},
+
+    #[error("Royalty and platform fee must not exceed the sale price")]
+    InvalidRoyalty {},
+
+    #[error("Invalid lease duration")]
+    InvalidLease {},
+
+    #[error("Tile is not offered for lease")]
+    NoLeaseOffer {},
+
+    #[error("Tile is leased until {until}")]
+    LeaseActive { until: Expiration },
+
+    #[error("Tile is reserved for an auction")]
+    TileReserved {},
+
+    #[error("Withdrawal must name at least one non-zero amount")]
+    InvalidWithdrawal {},
+
+    #[error("Use ProposeOwner to change the owner")]
+    OwnerChangeRequiresProposal {},
+
+    #[error("Invalid wall dimensions")]
+    InvalidDimensions {},
+
+    #[error("Cannot migrate from contract {contract}")]
+    InvalidMigrationContract { contract: String },
+
+    #[error("Cannot migrate from version {from} to older version {to}")]
+    CannotDowngrade { from: String, to: String },
+
+    #[error("Invalid contract version {version}")]
+    InvalidVersion { version: String },
+
+    #[error("Migration is not complete yet")]
+    MigrationPending {},
 }
 
 impl From<CW721ContractError> for ContractError {
@@ -1,17 +1,34 @@
-use crate::query as QueryHandler;
 use cosmwasm_std::{
-    from_binary, Addr, Coin, Deps, DepsMut, Empty, Env, MessageInfo, Response, StdResult,
+    Addr, BankMsg, Binary, Coin, Decimal, Deps, DepsMut, Empty, Env, MessageInfo, Order, Response,
+    StdResult, Storage, Timestamp, Uint128,
 };
-use cw721::{ContractInfoResponse, OwnerOfResponse};
-use cw721_base::{state::TokenInfo, Cw721Contract};
+use cw0::Duration;
+use cw721::{ContractInfoResponse, Cw721ReceiveMsg, Expiration};
+use cw721_base::state::{Approval, TokenInfo};
+use cw721_base::Cw721Contract;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
 
 use crate::error::ContractError;
-use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
-use crate::state::{config_read, config_store, tokens, Color, Config, PixelExtension};
+use crate::msg::{ExecuteMsg, InstantiateMsg, MintTile, PixelDataChange, PixelUpdate, Region};
+use crate::pricing::{mint_price, total_mint_price, PricingCurve};
+use crate::state::{
+    config_read, config_store, dutch_auction_count, dutch_auctions, editors, encode_color_map,
+    english_auctions, history, is_valid_palette, last_edits, lease_offers, leases, listings,
+    parse_token_id, price_zones, reserved_tiles, tokens, Bid, Color, ColorMap, Config,
+    DutchAuction, EditorApproval, EnglishAuction, HistoryEntry, LastEdit, Lease, LeaseOffer,
+    Listing, PixelExtension, PixelMode, PixelTokenInfo, PriceZone, Royalty, DEFAULT_TILE_SIZE,
+    DEFAULT_WALL_HEIGHT, DEFAULT_WALL_WIDTH, MAX_HISTORY, MAX_ROYALTY_BPS,
+};
+use cw_storage_plus::{U16Key, U64Key};
 
 const PIXEL: &str = "pixel";
 
-pub fn instantiate(deps: DepsMut, info: MessageInfo, msg: InstantiateMsg) -> StdResult<Response> {
+pub fn instantiate(
+    deps: DepsMut,
+    info: MessageInfo,
+    msg: InstantiateMsg,
+) -> Result<Response, ContractError> {
     let cw721_contract = Cw721Contract::<PixelExtension, Empty>::default();
 
     let contract_info = ContractInfoResponse {
@@ -25,7 +42,31 @@ pub fn instantiate(deps: DepsMut, info: MessageInfo, msg: InstantiateMsg) -> Std
     let config = Config {
         owner: deps.api.addr_canonicalize(info.sender.as_str())?,
         mint_fee: msg.mint_fee,
+        width: msg.width.unwrap_or(DEFAULT_WALL_WIDTH),
+        height: msg.height.unwrap_or(DEFAULT_WALL_HEIGHT),
+        tile_size: msg.tile_size.unwrap_or(DEFAULT_TILE_SIZE),
+        palette: msg.palette,
+        edit_cooldown: msg.edit_cooldown,
+        edit_fee: msg.edit_fee.filter(|fee| !fee.amount.is_zero()),
+        pricing: msg.pricing.unwrap_or_default(),
+        pending_owner: None,
+        platform_fee: msg.platform_fee.unwrap_or_default(),
+        royalty: msg.royalty.filter(|royalty| royalty.bps > 0),
     };
+    // Positions are u16, so the wall can hold at most u16::MAX + 1 tiles.
+    if config.width == 0
+        || config.height == 0
+        || config.tile_count() > u16::MAX as u32 + 1
+        || config.tile_size == 0
+    {
+        return Err(ContractError::InvalidDimensions {});
+    }
+    if let Some(palette) = &config.palette {
+        if !is_valid_palette(palette) {
+            return Err(ContractError::InvalidPalette {});
+        }
+    }
+    check_sale_fees(deps.as_ref(), &config)?;
     config_store(deps.storage).save(&config)?;
 
     Ok(Response::new()
@@ -38,68 +79,216 @@ pub fn execute_mint(
     info: MessageInfo,
     env: Env,
     position: u16,
-    color_map: Option<[[Color; 5]; 5]>,
+    color_map: Option<ColorMap>,
+    pixels: Option<Binary>,
     url: Option<String>,
 ) -> Result<Response, ContractError> {
-    let cw721_contract = Cw721Contract::<PixelExtension, Empty>::default();
+    let config = config_read(deps.storage).load()?;
 
-    if position >= 40_000 {
+    if !config.is_valid_position(position) {
         return Err(ContractError::InvalidTokenRange {});
     }
 
-    if get_owner(deps.as_ref(), env, position).is_some() {
+    if tokens()
+        .may_load(deps.storage, U16Key::new(position))?
+        .is_some()
+    {
         return Err(ContractError::Claimed {});
     }
+    check_not_reserved(deps.storage, &env, position)?;
+
+    let pixels = encode_pixels(&config, color_map, pixels)?;
+
+    let cw721_contract = Cw721Contract::<PixelExtension, Empty>::default();
+    let zones = price_zones().may_load(deps.storage)?.unwrap_or_default();
+    let minted = cw721_contract.token_count(deps.storage)?;
+    let price = mint_price(&config, &zones, position, minted)?;
+    let refund = check_sufficient_funds(info.funds, &price)?;
+
+    let extension = mint_tile(deps.storage, &config, &info.sender, position, pixels, url)?;
+
+    let response = Response::new()
+        .add_attribute("action", "mint_pixel")
+        .add_attribute("minter", info.sender.clone())
+        .add_attribute("mint_fee", format!("{:?}", price))
+        .add_attribute("token_id", position.to_string())
+        .add_attribute("url", extension.url)
+        .add_attribute("pixels", extension.pixels.to_base64());
+
+    Ok(add_refund(response, &info.sender, refund))
+}
+
+pub fn execute_batch_mint(
+    deps: DepsMut,
+    info: MessageInfo,
+    env: Env,
+    tiles: Vec<MintTile>,
+) -> Result<Response, ContractError> {
+    if tiles.is_empty() {
+        return Err(ContractError::NoTiles {});
+    }
+
+    let config = config_read(deps.storage).load()?;
+    let mut positions = BTreeSet::new();
+    for tile in tiles.iter() {
+        if !config.is_valid_position(tile.token_id) {
+            return Err(ContractError::InvalidTokenRange {});
+        }
+        if !positions.insert(tile.token_id)
+            || tokens()
+                .may_load(deps.storage, U16Key::new(tile.token_id))?
+                .is_some()
+        {
+            return Err(ContractError::Claimed {});
+        }
+        check_not_reserved(deps.storage, &env, tile.token_id)?;
+        encode_pixels(&config, tile.color_map.clone(), tile.pixels.clone())?;
+    }
 
+    mint_paid_tiles(deps, info, tiles, "batch_mint_pixels")
+}
+
+pub fn execute_mint_region(
+    deps: DepsMut,
+    info: MessageInfo,
+    env: Env,
+    region: Region,
+    color_maps: Option<Vec<ColorMap>>,
+) -> Result<Response, ContractError> {
     let config = config_read(deps.storage).load()?;
+    if region.width == 0 || region.height == 0 {
+        return Err(ContractError::NoTiles {});
+    }
+    if !region.is_on_wall(&config) {
+        return Err(ContractError::InvalidTokenRange {});
+    }
 
-    check_sufficient_funds(info.funds, &config.mint_fee)?;
+    let positions = region.positions(&config);
+    if let Some(color_maps) = &color_maps {
+        if color_maps.len() != positions.len() {
+            return Err(ContractError::InvalidColorMaps {});
+        }
+        if !color_maps
+            .iter()
+            .all(|color_map| config.is_valid_color_map(color_map))
+        {
+            return Err(ContractError::InvalidColorMapSize {});
+        }
+    }
 
-    let new_color_map = {
-        if let Some(color_map) = color_map {
-            color_map
-        } else {
-            [[Color {
-                r: 0,
-                g: 0,
-                b: 0,
-                a: 0,
-            }; 5]; 5]
+    let mut claimed = vec![];
+    for position in positions.iter() {
+        if tokens()
+            .may_load(deps.storage, U16Key::new(*position))?
+            .is_some()
+        {
+            claimed.push(*position);
         }
+    }
+    if !claimed.is_empty() {
+        return Err(ContractError::RegionClaimed { token_ids: claimed });
+    }
+    for position in positions.iter() {
+        check_not_reserved(deps.storage, &env, *position)?;
+    }
+
+    let tiles = match color_maps {
+        Some(color_maps) => positions
+            .into_iter()
+            .zip(color_maps)
+            .map(|(token_id, color_map)| MintTile {
+                token_id,
+                color_map: Some(color_map),
+                pixels: None,
+                url: None,
+            })
+            .collect(),
+        None => positions
+            .into_iter()
+            .map(|token_id| MintTile {
+                token_id,
+                color_map: None,
+                pixels: None,
+                url: None,
+            })
+            .collect(),
     };
 
-    let new_url = {
-        if let Some(url) = url {
-            url
-        } else {
-            String::from("")
-        }
+    mint_paid_tiles(deps, info, tiles, "mint_region")
+}
+
+/// Charges the current mint price of each tile in `tiles` and mints them to the sender. All
+/// tiles must already have been validated.
+fn mint_paid_tiles(
+    deps: DepsMut,
+    info: MessageInfo,
+    tiles: Vec<MintTile>,
+    action: &str,
+) -> Result<Response, ContractError> {
+    let config = config_read(deps.storage).load()?;
+    let cw721_contract = Cw721Contract::<PixelExtension, Empty>::default();
+    let minted = cw721_contract.token_count(deps.storage)?;
+    let zones = price_zones().may_load(deps.storage)?.unwrap_or_default();
+    let positions: Vec<u16> = tiles.iter().map(|tile| tile.token_id).collect();
+    let total_fee = total_mint_price(&config, &zones, &positions, minted)?;
+
+    let refund = check_sufficient_funds(info.funds, &total_fee)?;
+
+    let mut token_ids = vec![];
+    for tile in tiles {
+        let pixels = encode_pixels(&config, tile.color_map, tile.pixels)?;
+        mint_tile(
+            deps.storage,
+            &config,
+            &info.sender,
+            tile.token_id,
+            pixels,
+            tile.url,
+        )?;
+        token_ids.push(tile.token_id.to_string());
+    }
+
+    let response = Response::new()
+        .add_attribute("action", action)
+        .add_attribute("minter", info.sender.clone())
+        .add_attribute("mint_fee", format!("{:?}", total_fee))
+        .add_attribute("token_ids", token_ids.join(","));
+
+    Ok(add_refund(response, &info.sender, refund))
+}
+
+/// Stores a new tile owned by `owner`, defaulting to transparent pixels and an empty url.
+fn mint_tile(
+    storage: &mut dyn Storage,
+    config: &Config,
+    owner: &Addr,
+    position: u16,
+    pixels: Option<(Binary, PixelMode)>,
+    url: Option<String>,
+) -> Result<PixelExtension, ContractError> {
+    let cw721_contract = Cw721Contract::<PixelExtension, Empty>::default();
+
+    let (pixels, mode) = pixels.unwrap_or_else(|| (config.empty_pixels(), PixelMode::Rgba));
+    let extension = PixelExtension {
+        pixels,
+        mode,
+        url: url.unwrap_or_default(),
     };
 
-    let token_id = position.to_string();
     let token = TokenInfo::<PixelExtension> {
-        owner: info.sender.clone(),
+        owner: owner.clone(),
         approvals: vec![],
         token_uri: None,
-        extension: PixelExtension {
-            pixel_colors: new_color_map,
-            url: new_url.clone(),
-        },
+        extension: extension.clone(),
     };
-    tokens().update(deps.storage, &token_id, |old| match old {
+    tokens().update(storage, U16Key::new(position), |old| match old {
         Some(_) => Err(ContractError::Claimed {}),
         None => Ok(token),
     })?;
 
-    cw721_contract.increment_tokens(deps.storage)?;
+    cw721_contract.increment_tokens(storage)?;
 
-    Ok(Response::new()
-        .add_attribute("action", "mint_pixel")
-        .add_attribute("minter", info.sender)
-        .add_attribute("mint_fee", format!("{:?}", config.mint_fee))
-        .add_attribute("token_id", token_id)
-        .add_attribute("url", new_url)
-        .add_attribute("color_map", format!("{:?}", new_color_map)))
+    Ok(extension)
 }
 
 pub fn execute_change_pixel_data(
@@ -107,21 +296,20 @@ pub fn execute_change_pixel_data(
     info: MessageInfo,
     env: Env,
     position: u16,
-    color_map: Option<[[Color; 5]; 5]>,
+    color_map: Option<ColorMap>,
+    pixels: Option<Binary>,
     url: Option<String>,
 ) -> Result<Response, ContractError> {
-    if !token_minted(deps.as_ref(), env.clone(), position) {
-        return Err(ContractError::DoesNotExist {});
-    }
-
-    let owner = get_owner(deps.as_ref(), env, position).unwrap();
+    let token = tokens()
+        .may_load(deps.storage, U16Key::new(position))?
+        .ok_or(ContractError::DoesNotExist {})?;
 
-    if owner != info.sender {
-        return Err(ContractError::Unauthorized {});
-    }
+    check_can_edit(deps.as_ref(), &env, &info.sender, position, &token)?;
 
-    let token_id = position.to_string();
-    let token = tokens().load(deps.storage, &token_id)?;
+    let config = config_read(deps.storage).load()?;
+    check_cooldown(deps.storage, &config, &env, position)?;
+    let pixels = encode_pixels(&config, color_map, pixels)?;
+    let charged = charge_edit_fee(&config, info.funds.clone(), 1)?;
 
     let extension = token.clone().extension;
 
@@ -130,51 +318,1054 @@ pub fn execute_change_pixel_data(
         Some(url) => url,
     };
 
-    let new_color_map = match color_map {
-        None => extension.pixel_colors,
-        Some(color_map) => color_map,
+    let (new_pixels, new_mode) = match pixels {
+        None => (extension.pixels, extension.mode),
+        Some(pixels) => pixels,
     };
 
     let updated_extension = PixelExtension {
-        pixel_colors: new_color_map,
+        pixels: new_pixels.clone(),
+        mode: new_mode,
         url: new_url.clone(),
     };
 
     let updated_token = TokenInfo::<PixelExtension> {
-        owner: Addr::unchecked(owner),
+        owner: token.clone().owner,
         approvals: token.clone().approvals,
         token_uri: token.clone().token_uri,
         extension: updated_extension,
     };
 
-    tokens().replace(deps.storage, &token_id, Some(&updated_token), Some(&token))?;
+    tokens().replace(
+        deps.storage,
+        U16Key::new(position),
+        Some(&updated_token),
+        Some(&token),
+    )?;
+    record_edit(deps.storage, &env, position, token.extension)?;
 
-    Ok(Response::new()
+    let response = Response::new()
         .add_attribute("action", "change pixel data")
-        .add_attribute("token_id", token_id)
-        .add_attribute("color_map", format!("{:?}", new_color_map))
-        .add_attribute("url", new_url))
+        .add_attribute("token_id", position.to_string())
+        .add_attribute("pixels", new_pixels.to_base64())
+        .add_attribute("url", new_url);
+
+    Ok(add_edit_fee(response, &info.sender, charged))
+}
+
+pub fn execute_batch_change_pixel_data(
+    deps: DepsMut,
+    info: MessageInfo,
+    env: Env,
+    changes: Vec<PixelDataChange>,
+) -> Result<Response, ContractError> {
+    if changes.is_empty() {
+        return Err(ContractError::NoTiles {});
+    }
+
+    // Authorize every tile before changing any of them.
+    let config = config_read(deps.storage).load()?;
+    let mut originals = BTreeMap::new();
+    let mut new_pixels = vec![];
+    for change in changes.iter() {
+        new_pixels.push(encode_pixels(
+            &config,
+            change.color_map.clone(),
+            change.pixels.clone(),
+        )?);
+        if originals.contains_key(&change.token_id) {
+            continue;
+        }
+        let token = tokens()
+            .may_load(deps.storage, U16Key::new(change.token_id))?
+            .ok_or(ContractError::DoesNotExist {})?;
+        check_can_edit(deps.as_ref(), &env, &info.sender, change.token_id, &token)?;
+        check_cooldown(deps.storage, &config, &env, change.token_id)?;
+        originals.insert(change.token_id, token);
+    }
+    let charged = charge_edit_fee(&config, info.funds.clone(), originals.len())?;
+
+    let mut updated = originals.clone();
+    for (change, pixels) in changes.into_iter().zip(new_pixels) {
+        let token = updated.get_mut(&change.token_id).unwrap();
+        if let Some((pixels, mode)) = pixels {
+            token.extension.pixels = pixels;
+            token.extension.mode = mode;
+        }
+        if let Some(url) = change.url {
+            token.extension.url = url;
+        }
+    }
+
+    for (position, token) in updated.iter() {
+        tokens().replace(
+            deps.storage,
+            U16Key::new(*position),
+            Some(token),
+            originals.get(position),
+        )?;
+    }
+    for (position, token) in originals {
+        record_edit(deps.storage, &env, position, token.extension)?;
+    }
+
+    let token_ids: Vec<String> = updated
+        .keys()
+        .map(|position| position.to_string())
+        .collect();
+    let response = Response::new()
+        .add_attribute("action", "batch change pixel data")
+        .add_attribute("token_ids", token_ids.join(","));
+
+    Ok(add_edit_fee(response, &info.sender, charged))
+}
+
+pub fn execute_undo_pixel_change(
+    deps: DepsMut,
+    info: MessageInfo,
+    env: Env,
+    position: u16,
+) -> Result<Response, ContractError> {
+    let token = tokens()
+        .may_load(deps.storage, U16Key::new(position))?
+        .ok_or(ContractError::DoesNotExist {})?;
+    check_can_edit(deps.as_ref(), &env, &info.sender, position, &token)?;
+    let config = config_read(deps.storage).load()?;
+    check_cooldown(deps.storage, &config, &env, position)?;
+    let charged = charge_edit_fee(&config, info.funds.clone(), 1)?;
+
+    let key = U16Key::new(position);
+    let mut entries = history()
+        .may_load(deps.storage, key.clone())?
+        .unwrap_or_default();
+    let entry = entries.pop().ok_or(ContractError::NoHistory {})?;
+    if entries.is_empty() {
+        history().remove(deps.storage, key.clone());
+    } else {
+        history().save(deps.storage, key.clone(), &entries)?;
+    }
+
+    let mut updated = token.clone();
+    updated.extension = entry.extension;
+    tokens().replace(deps.storage, key.clone(), Some(&updated), Some(&token))?;
+    last_edits().save(deps.storage, key, &LastEdit::from(&env.block))?;
+
+    let response = Response::new()
+        .add_attribute("action", "undo pixel change")
+        .add_attribute("token_id", position.to_string())
+        .add_attribute("pixels", updated.extension.pixels.to_base64())
+        .add_attribute("url", updated.extension.url);
+
+    Ok(add_edit_fee(response, &info.sender, charged))
+}
+
+pub fn execute_set_pixels(
+    deps: DepsMut,
+    info: MessageInfo,
+    env: Env,
+    position: u16,
+    pixels: Vec<PixelUpdate>,
+) -> Result<Response, ContractError> {
+    if pixels.is_empty() {
+        return Err(ContractError::NoPixels {});
+    }
+
+    let token = tokens()
+        .may_load(deps.storage, U16Key::new(position))?
+        .ok_or(ContractError::DoesNotExist {})?;
+    check_can_edit(deps.as_ref(), &env, &info.sender, position, &token)?;
+
+    let config = config_read(deps.storage).load()?;
+    check_cooldown(deps.storage, &config, &env, position)?;
+    let charged = charge_edit_fee(&config, info.funds.clone(), 1)?;
+    let mut updated = token.clone();
+    let mut data = updated.extension.pixels.to_vec();
+    let mut coordinates = vec![];
+    for pixel in pixels {
+        if pixel.x >= config.tile_size || pixel.y >= config.tile_size {
+            return Err(ContractError::PixelOutOfRange {
+                x: pixel.x,
+                y: pixel.y,
+            });
+        }
+        // A palette restricts the colours of every tile, whatever its encoding.
+        let index = match &config.palette {
+            Some(_) => Some(
+                config
+                    .palette_index(&pixel.color)
+                    .ok_or(ContractError::ColorNotInPalette {})?,
+            ),
+            None => None,
+        };
+        let offset = pixel.y as usize * config.tile_size as usize + pixel.x as usize;
+        match (updated.extension.mode, index) {
+            (PixelMode::Rgba, _) => {
+                data[offset * 4..offset * 4 + 4].copy_from_slice(&pixel.color.to_rgba())
+            }
+            (PixelMode::Palette, Some(index)) => data[offset] = index,
+            (PixelMode::Palette, None) => return Err(ContractError::ColorNotInPalette {}),
+        }
+        coordinates.push(format!("{},{}", pixel.x, pixel.y));
+    }
+    updated.extension.pixels = Binary(data);
+
+    tokens().replace(
+        deps.storage,
+        U16Key::new(position),
+        Some(&updated),
+        Some(&token),
+    )?;
+    record_edit(deps.storage, &env, position, token.extension)?;
+
+    let response = Response::new()
+        .add_attribute("action", "set pixels")
+        .add_attribute("token_id", position.to_string())
+        .add_attribute("pixels", coordinates.join(";"));
+
+    Ok(add_edit_fee(response, &info.sender, charged))
+}
+
+pub fn execute_approve_editor(
+    deps: DepsMut,
+    info: MessageInfo,
+    env: Env,
+    position: u16,
+    editor: String,
+    expires: Option<Expiration>,
+) -> Result<Response, ContractError> {
+    let token = tokens()
+        .may_load(deps.storage, U16Key::new(position))?
+        .ok_or(ContractError::DoesNotExist {})?;
+    check_can_approve(deps.as_ref(), &env, &info.sender, &token)?;
+
+    let expires = check_not_expired(expires, &env)?;
+
+    let editor = deps.api.addr_validate(&editor)?;
+    let approval = EditorApproval {
+        editor: editor.clone(),
+        granter: token.owner,
+        expires,
+    };
+    editors().save(deps.storage, (U16Key::new(position), &editor), &approval)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "approve_editor")
+        .add_attribute("sender", info.sender)
+        .add_attribute("editor", editor)
+        .add_attribute("token_id", position.to_string()))
+}
+
+pub fn execute_revoke_editor(
+    deps: DepsMut,
+    info: MessageInfo,
+    env: Env,
+    position: u16,
+    editor: String,
+) -> Result<Response, ContractError> {
+    let token = tokens()
+        .may_load(deps.storage, U16Key::new(position))?
+        .ok_or(ContractError::DoesNotExist {})?;
+    check_can_approve(deps.as_ref(), &env, &info.sender, &token)?;
+
+    let editor = deps.api.addr_validate(&editor)?;
+    editors().remove(deps.storage, (U16Key::new(position), &editor));
+
+    Ok(Response::new()
+        .add_attribute("action", "revoke_editor")
+        .add_attribute("sender", info.sender)
+        .add_attribute("editor", editor)
+        .add_attribute("token_id", position.to_string()))
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn execute_update_config(
     deps: DepsMut,
     info: MessageInfo,
     owner: Option<String>,
+    mint_fee: Option<Coin>,
+    edit_fee: Option<Coin>,
+    edit_cooldown: Option<Duration>,
+    pricing: Option<PricingCurve>,
+    platform_fee: Option<Decimal>,
+    royalty: Option<Royalty>,
+) -> Result<Response, ContractError> {
+    let mut config = config_read(deps.storage).load()?;
+    if config.owner != deps.api.addr_canonicalize(info.sender.as_str())? {
+        return Err(ContractError::Unauthorized {});
+    }
+    if owner.is_some() {
+        return Err(ContractError::OwnerChangeRequiresProposal {});
+    }
+
+    let mut response = Response::new().add_attribute("action", "update_config");
+    if let Some(mint_fee) = mint_fee {
+        let zones = price_zones().may_load(deps.storage)?.unwrap_or_default();
+        if zones.iter().any(|zone| zone.price.denom != mint_fee.denom) {
+            return Err(ContractError::InvalidPriceZone {});
+        }
+        response = response
+            .add_attribute("old_mint_fee", config.mint_fee.to_string())
+            .add_attribute("new_mint_fee", mint_fee.to_string());
+        config.mint_fee = mint_fee;
+    }
+    if let Some(edit_fee) = edit_fee {
+        // A zero fee makes editing free again.
+        let edit_fee = Some(edit_fee).filter(|fee| !fee.amount.is_zero());
+        response = response
+            .add_attribute("old_edit_fee", display_option(&config.edit_fee))
+            .add_attribute("new_edit_fee", display_option(&edit_fee));
+        config.edit_fee = edit_fee;
+    }
+    if let Some(edit_cooldown) = edit_cooldown {
+        // A zero cooldown allows editing in every block again.
+        let edit_cooldown = Some(edit_cooldown).filter(|cooldown| match cooldown {
+            Duration::Height(blocks) => *blocks > 0,
+            Duration::Time(seconds) => *seconds > 0,
+        });
+        response = response
+            .add_attribute("old_edit_cooldown", display_option(&config.edit_cooldown))
+            .add_attribute("new_edit_cooldown", display_option(&edit_cooldown));
+        config.edit_cooldown = edit_cooldown;
+    }
+    if let Some(pricing) = pricing {
+        response = response
+            .add_attribute("old_pricing", config.pricing.to_string())
+            .add_attribute("new_pricing", pricing.to_string());
+        config.pricing = pricing;
+    }
+    if let Some(platform_fee) = platform_fee {
+        response = response
+            .add_attribute("old_platform_fee", config.platform_fee.to_string())
+            .add_attribute("new_platform_fee", platform_fee.to_string());
+        config.platform_fee = platform_fee;
+    }
+    if let Some(royalty) = royalty {
+        // Zero basis points stop charging royalties.
+        let royalty = Some(royalty).filter(|royalty| royalty.bps > 0);
+        response = response
+            .add_attribute("old_royalty", display_option(&config.royalty))
+            .add_attribute("new_royalty", display_option(&royalty));
+        config.royalty = royalty;
+    }
+    check_sale_fees(deps.as_ref(), &config)?;
+    config_store(deps.storage).save(&config)?;
+
+    Ok(response)
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn execute_create_auction(
+    deps: DepsMut,
+    info: MessageInfo,
+    env: Env,
+    tiles: Vec<u16>,
+    start_price: Coin,
+    end_price: Coin,
+    start: Timestamp,
+    end: Timestamp,
+) -> Result<Response, ContractError> {
+    let config = config_read(deps.storage).load()?;
+    if config.owner != deps.api.addr_canonicalize(info.sender.as_str())? {
+        return Err(ContractError::Unauthorized {});
+    }
+    if tiles.is_empty()
+        || start >= end
+        || end <= env.block.time
+        || start_price.denom != end_price.denom
+        || start_price.amount < end_price.amount
+    {
+        return Err(ContractError::InvalidAuction {});
+    }
+
+    let mut positions = BTreeSet::new();
+    for position in tiles.iter() {
+        if !config.is_valid_position(*position) {
+            return Err(ContractError::InvalidTokenRange {});
+        }
+        if !positions.insert(*position)
+            || tokens()
+                .may_load(deps.storage, U16Key::new(*position))?
+                .is_some()
+        {
+            return Err(ContractError::Claimed {});
+        }
+        check_not_reserved(deps.storage, &env, *position)?;
+    }
+
+    let id = dutch_auction_count()
+        .may_load(deps.storage)?
+        .unwrap_or_default()
+        + 1;
+    dutch_auction_count().save(deps.storage, &id)?;
+    for position in tiles.iter() {
+        reserved_tiles().save(deps.storage, U16Key::new(*position), &id)?;
+    }
+    let auction = DutchAuction {
+        tiles,
+        start_price,
+        end_price,
+        start,
+        end,
+    };
+    dutch_auctions().save(deps.storage, U64Key::new(id), &auction)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "create_auction")
+        .add_attribute("auction_id", id.to_string())
+        .add_attribute("tiles", format!("{:?}", auction.tiles)))
+}
+
+pub fn execute_bid_auction(
+    deps: DepsMut,
+    info: MessageInfo,
+    env: Env,
+    auction_id: u64,
+    position: u16,
+) -> Result<Response, ContractError> {
+    let key = U64Key::new(auction_id);
+    let mut auction = dutch_auctions()
+        .may_load(deps.storage, key.clone())?
+        .ok_or(ContractError::AuctionNotActive {})?;
+    if !auction.is_active(&env.block) {
+        return Err(ContractError::AuctionNotActive {});
+    }
+    if !auction.tiles.contains(&position) {
+        return Err(ContractError::NotInAuction {});
+    }
+
+    let price = auction.price(env.block.time);
+    let refund = check_sufficient_funds(info.funds, &price)?;
+
+    auction.tiles.retain(|tile| *tile != position);
+    if auction.tiles.is_empty() {
+        dutch_auctions().remove(deps.storage, key);
+    } else {
+        dutch_auctions().save(deps.storage, key, &auction)?;
+    }
+    reserved_tiles().remove(deps.storage, U16Key::new(position));
+
+    let config = config_read(deps.storage).load()?;
+    mint_tile(deps.storage, &config, &info.sender, position, None, None)?;
+
+    let response = Response::new()
+        .add_attribute("action", "bid_auction")
+        .add_attribute("auction_id", auction_id.to_string())
+        .add_attribute("minter", info.sender.clone())
+        .add_attribute("price", format!("{:?}", price))
+        .add_attribute("token_id", position.to_string());
+
+    Ok(add_refund(response, &info.sender, refund))
+}
+
+pub fn execute_create_english_auction(
+    deps: DepsMut,
+    info: MessageInfo,
+    env: Env,
+    position: u16,
+    min_bid: Coin,
+    expires: Expiration,
+) -> Result<Response, ContractError> {
+    let config = config_read(deps.storage).load()?;
+    if config.owner != deps.api.addr_canonicalize(info.sender.as_str())? {
+        return Err(ContractError::Unauthorized {});
+    }
+    if matches!(expires, Expiration::Never {})
+        || expires.is_expired(&env.block)
+        || min_bid.amount.is_zero()
+    {
+        return Err(ContractError::InvalidAuction {});
+    }
+    if !config.is_valid_position(position) {
+        return Err(ContractError::InvalidTokenRange {});
+    }
+    if tokens()
+        .may_load(deps.storage, U16Key::new(position))?
+        .is_some()
+    {
+        return Err(ContractError::Claimed {});
+    }
+    check_not_reserved(deps.storage, &env, position)?;
+
+    let auction = EnglishAuction {
+        min_bid,
+        expires,
+        highest_bid: None,
+    };
+    english_auctions().save(deps.storage, U16Key::new(position), &auction)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "create_english_auction")
+        .add_attribute("token_id", position.to_string())
+        .add_attribute("min_bid", format!("{:?}", auction.min_bid))
+        .add_attribute("expires", auction.expires.to_string()))
+}
+
+pub fn execute_place_bid(
+    deps: DepsMut,
+    info: MessageInfo,
+    env: Env,
+    position: u16,
+) -> Result<Response, ContractError> {
+    let key = U16Key::new(position);
+    let mut auction = english_auctions()
+        .may_load(deps.storage, key.clone())?
+        .ok_or(ContractError::AuctionNotActive {})?;
+    if auction.expires.is_expired(&env.block) {
+        return Err(ContractError::AuctionNotActive {});
+    }
+
+    let denom = auction.min_bid.denom.clone();
+    let (bid, refund): (Vec<Coin>, Vec<Coin>) = info
+        .funds
+        .into_iter()
+        .filter(|coin| !coin.amount.is_zero())
+        .partition(|coin| coin.denom == denom);
+    let amount = bid
+        .iter()
+        .fold(Uint128::zero(), |total, coin| total + coin.amount);
+    let min_bid = match &auction.highest_bid {
+        Some(highest_bid) => Coin {
+            denom,
+            amount: highest_bid.amount.amount + Uint128::new(1),
+        },
+        None => auction.min_bid.clone(),
+    };
+    if amount < min_bid.amount {
+        return Err(ContractError::BidTooLow { min_bid });
+    }
+
+    let bid = Bid {
+        bidder: info.sender.clone(),
+        amount: Coin {
+            denom: min_bid.denom,
+            amount,
+        },
+    };
+    let outbid = auction.highest_bid.replace(bid.clone());
+    english_auctions().save(deps.storage, key, &auction)?;
+
+    let mut response = Response::new()
+        .add_attribute("action", "place_bid")
+        .add_attribute("token_id", position.to_string())
+        .add_attribute("bidder", info.sender.clone())
+        .add_attribute("amount", format!("{:?}", bid.amount));
+    if let Some(outbid) = outbid {
+        response = response
+            .add_attribute("outbid", outbid.bidder.clone())
+            .add_message(BankMsg::Send {
+                to_address: outbid.bidder.to_string(),
+                amount: vec![outbid.amount],
+            });
+    }
+
+    Ok(add_refund(response, &info.sender, refund))
+}
+
+/// Ends an English auction past its expiration, minting the tile to the highest bidder. The
+/// winning bid stays in the contract as a mint fee.
+pub fn execute_settle_auction(
+    deps: DepsMut,
+    env: Env,
+    position: u16,
+) -> Result<Response, ContractError> {
+    let key = U16Key::new(position);
+    let auction = english_auctions()
+        .may_load(deps.storage, key.clone())?
+        .ok_or(ContractError::AuctionNotActive {})?;
+    if !auction.expires.is_expired(&env.block) {
+        return Err(ContractError::AuctionNotEnded {});
+    }
+    english_auctions().remove(deps.storage, key);
+
+    let response = Response::new()
+        .add_attribute("action", "settle_auction")
+        .add_attribute("token_id", position.to_string());
+    match auction.highest_bid {
+        Some(bid) => {
+            let config = config_read(deps.storage).load()?;
+            mint_tile(deps.storage, &config, &bid.bidder, position, None, None)?;
+            Ok(response
+                .add_attribute("minter", bid.bidder)
+                .add_attribute("price", format!("{:?}", bid.amount)))
+        }
+        None => Ok(response),
+    }
+}
+
+/// Fails if the tile at `position` is reserved for an auction that has not ended yet. Tiles of
+/// English auctions stay reserved until the auction is settled.
+fn check_not_reserved(
+    storage: &dyn Storage,
+    env: &Env,
+    position: u16,
+) -> Result<(), ContractError> {
+    if english_auctions()
+        .may_load(storage, U16Key::new(position))?
+        .is_some()
+    {
+        return Err(ContractError::TileReserved {});
+    }
+    let auction_id = match reserved_tiles().may_load(storage, U16Key::new(position))? {
+        Some(auction_id) => auction_id,
+        None => return Ok(()),
+    };
+    match dutch_auctions().may_load(storage, U64Key::new(auction_id))? {
+        Some(auction) if env.block.time < auction.end => Err(ContractError::TileReserved {}),
+        _ => Ok(()),
+    }
+}
+
+pub fn execute_list_tile(
+    deps: DepsMut,
+    info: MessageInfo,
+    position: u16,
+    price: Coin,
+) -> Result<Response, ContractError> {
+    let token = tokens()
+        .may_load(deps.storage, U16Key::new(position))?
+        .ok_or(ContractError::DoesNotExist {})?;
+    if token.owner != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let listing = Listing {
+        seller: info.sender,
+        price,
+    };
+    listings().save(deps.storage, U16Key::new(position), &listing)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "list_tile")
+        .add_attribute("token_id", position.to_string())
+        .add_attribute("seller", listing.seller)
+        .add_attribute("price", format!("{:?}", listing.price)))
+}
+
+/// Transfers a listed tile to the sender, as long as the seller still owns it, and pays the
+/// seller the price minus the platform fee and the royalty. The fee stays in the contract.
+pub fn execute_buy_tile(
+    deps: DepsMut,
+    info: MessageInfo,
+    position: u16,
+) -> Result<Response, ContractError> {
+    let key = U16Key::new(position);
+    let listing = listings()
+        .may_load(deps.storage, key.clone())?
+        .ok_or(ContractError::NotListed {})?;
+    let mut token = tokens()
+        .may_load(deps.storage, key.clone())?
+        .ok_or(ContractError::DoesNotExist {})?;
+    if token.owner != listing.seller {
+        return Err(ContractError::NotListed {});
+    }
+
+    let refund = check_sufficient_funds(info.funds, &listing.price)?;
+    let config = config_read(deps.storage).load()?;
+    let fee = listing.price.amount * config.platform_fee;
+    let royalty = match &config.royalty {
+        Some(royalty) => royalty.amount(listing.price.amount),
+        None => Uint128::zero(),
+    };
+    let proceeds = Coin {
+        denom: listing.price.denom.clone(),
+        amount: listing.price.amount - fee - royalty,
+    };
+
+    token.owner = info.sender.clone();
+    token.approvals = vec![];
+    tokens().save(deps.storage, key.clone(), &token)?;
+    listings().remove(deps.storage, key);
+
+    let mut response = Response::new()
+        .add_attribute("action", "buy_tile")
+        .add_attribute("token_id", position.to_string())
+        .add_attribute("seller", listing.seller.clone())
+        .add_attribute("buyer", info.sender.clone())
+        .add_attribute("price", format!("{:?}", listing.price))
+        .add_attribute("platform_fee", fee.to_string());
+    if !proceeds.amount.is_zero() {
+        response = response.add_message(BankMsg::Send {
+            to_address: listing.seller.to_string(),
+            amount: vec![proceeds],
+        });
+    }
+    match config.royalty {
+        Some(Royalty { recipient, .. }) if !royalty.is_zero() => {
+            response = response
+                .add_attribute("royalty", royalty.to_string())
+                .add_message(BankMsg::Send {
+                    to_address: recipient.to_string(),
+                    amount: vec![Coin {
+                        denom: listing.price.denom,
+                        amount: royalty,
+                    }],
+                });
+        }
+        _ => {}
+    }
+
+    Ok(add_refund(response, &info.sender, refund))
+}
+
+pub fn execute_cancel_listing(
+    deps: DepsMut,
+    info: MessageInfo,
+    position: u16,
+) -> Result<Response, ContractError> {
+    let key = U16Key::new(position);
+    let listing = listings()
+        .may_load(deps.storage, key.clone())?
+        .ok_or(ContractError::NotListed {})?;
+    if listing.seller != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    listings().remove(deps.storage, key);
+
+    Ok(Response::new()
+        .add_attribute("action", "cancel_listing")
+        .add_attribute("token_id", position.to_string()))
+}
+
+pub fn execute_offer_lease(
+    deps: DepsMut,
+    info: MessageInfo,
+    env: Env,
+    position: u16,
+    price: Coin,
+    duration: Duration,
+) -> Result<Response, ContractError> {
+    let token = tokens()
+        .may_load(deps.storage, U16Key::new(position))?
+        .ok_or(ContractError::DoesNotExist {})?;
+    if token.owner != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    if matches!(duration, Duration::Height(0) | Duration::Time(0)) {
+        return Err(ContractError::InvalidLease {});
+    }
+
+    let offer = LeaseOffer {
+        lessor: info.sender,
+        price,
+        duration,
+    };
+    if offer.expiration(&env.block).is_none() {
+        return Err(ContractError::InvalidLease {});
+    }
+    lease_offers().save(deps.storage, U16Key::new(position), &offer)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "offer_lease")
+        .add_attribute("token_id", position.to_string())
+        .add_attribute("lessor", offer.lessor)
+        .add_attribute("price", format!("{:?}", offer.price))
+        .add_attribute("duration", offer.duration.to_string()))
+}
+
+/// Leases a tile to the sender as offered by its owner, who is paid right away.
+pub fn execute_accept_lease(
+    deps: DepsMut,
+    info: MessageInfo,
+    env: Env,
+    position: u16,
+) -> Result<Response, ContractError> {
+    let key = U16Key::new(position);
+    let offer = lease_offers()
+        .may_load(deps.storage, key.clone())?
+        .ok_or(ContractError::NoLeaseOffer {})?;
+    // Offers lapse when the tile changes hands.
+    let token = tokens()
+        .may_load(deps.storage, key.clone())?
+        .ok_or(ContractError::DoesNotExist {})?;
+    if token.owner != offer.lessor {
+        return Err(ContractError::NoLeaseOffer {});
+    }
+    if let Some(lease) = leases().may_load(deps.storage, key.clone())? {
+        if lease.is_active(&env.block) {
+            return Err(ContractError::LeaseActive {
+                until: lease.expires,
+            });
+        }
+    }
+
+    let refund = check_sufficient_funds(info.funds, &offer.price)?;
+    let lease = Lease {
+        lessee: info.sender.clone(),
+        expires: offer
+            .expiration(&env.block)
+            .ok_or(ContractError::InvalidLease {})?,
+    };
+    leases().save(deps.storage, key.clone(), &lease)?;
+    lease_offers().remove(deps.storage, key);
+
+    let mut response = Response::new()
+        .add_attribute("action", "accept_lease")
+        .add_attribute("token_id", position.to_string())
+        .add_attribute("lessor", offer.lessor.clone())
+        .add_attribute("lessee", info.sender.clone())
+        .add_attribute("price", format!("{:?}", offer.price))
+        .add_attribute("expires", lease.expires.to_string());
+    if !offer.price.amount.is_zero() {
+        response = response.add_message(BankMsg::Send {
+            to_address: offer.lessor.to_string(),
+            amount: vec![offer.price],
+        });
+    }
+
+    Ok(add_refund(response, &info.sender, refund))
+}
+
+pub fn execute_cancel_lease_offer(
+    deps: DepsMut,
+    info: MessageInfo,
+    position: u16,
+) -> Result<Response, ContractError> {
+    let key = U16Key::new(position);
+    let token = tokens()
+        .may_load(deps.storage, key.clone())?
+        .ok_or(ContractError::DoesNotExist {})?;
+    if token.owner != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    if lease_offers()
+        .may_load(deps.storage, key.clone())?
+        .is_none()
+    {
+        return Err(ContractError::NoLeaseOffer {});
+    }
+    lease_offers().remove(deps.storage, key);
+
+    Ok(Response::new()
+        .add_attribute("action", "cancel_lease_offer")
+        .add_attribute("token_id", position.to_string()))
+}
+
+/// Proposes `owner` as the new config owner. The transfer only takes effect once `owner`
+/// accepts it, so a mistyped address cannot lock out administration.
+pub fn execute_propose_owner(
+    deps: DepsMut,
+    info: MessageInfo,
+    owner: String,
+) -> Result<Response, ContractError> {
+    let api = deps.api;
+    let owner = api.addr_validate(&owner)?;
+    config_store(deps.storage).update(|mut config| {
+        if config.owner != api.addr_canonicalize(info.sender.as_str())? {
+            return Err(ContractError::Unauthorized {});
+        }
+        config.pending_owner = Some(api.addr_canonicalize(owner.as_str())?);
+        Ok(config)
+    })?;
+
+    Ok(Response::new()
+        .add_attribute("action", "propose_owner")
+        .add_attribute("pending_owner", owner))
+}
+
+pub fn execute_accept_ownership(
+    deps: DepsMut,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let mut config = config_read(deps.storage).load()?;
+    let sender = deps.api.addr_canonicalize(info.sender.as_str())?;
+    if config.pending_owner != Some(sender.clone()) {
+        return Err(ContractError::Unauthorized {});
+    }
+    let previous_owner = deps.api.addr_humanize(&config.owner)?;
+    config.owner = sender;
+    config.pending_owner = None;
+    config_store(deps.storage).save(&config)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "accept_ownership")
+        .add_attribute("previous_owner", previous_owner)
+        .add_attribute("owner", info.sender))
+}
+
+pub fn execute_cancel_ownership_proposal(
+    deps: DepsMut,
+    info: MessageInfo,
 ) -> Result<Response, ContractError> {
     let api = deps.api;
     config_store(deps.storage).update(|mut config| {
         if config.owner != api.addr_canonicalize(info.sender.as_str())? {
             return Err(ContractError::Unauthorized {});
         }
+        config.pending_owner = None;
+        Ok(config)
+    })?;
+
+    Ok(Response::new().add_attribute("action", "cancel_ownership_proposal"))
+}
+
+/// Replaces all price zones. Only callable by the config owner.
+pub fn execute_set_price_zones(
+    deps: DepsMut,
+    info: MessageInfo,
+    zones: Vec<PriceZone>,
+) -> Result<Response, ContractError> {
+    let config = config_read(deps.storage).load()?;
+    if config.owner != deps.api.addr_canonicalize(info.sender.as_str())? {
+        return Err(ContractError::Unauthorized {});
+    }
+    if !zones.iter().all(|zone| zone.is_valid(&config)) {
+        return Err(ContractError::InvalidPriceZone {});
+    }
+    price_zones().save(deps.storage, &zones)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_price_zones")
+        .add_attribute("zones", zones.len().to_string()))
+}
 
-        if let Some(owner) = owner {
-            config.owner = api.addr_canonicalize(&owner)?;
+pub fn execute_update_palette(
+    deps: DepsMut,
+    info: MessageInfo,
+    palette: Option<Vec<Color>>,
+) -> Result<Response, ContractError> {
+    if let Some(palette) = &palette {
+        if !is_valid_palette(palette) {
+            return Err(ContractError::InvalidPalette {});
         }
+    }
 
+    let api = deps.api;
+    config_store(deps.storage).update(|mut config| {
+        if config.owner != api.addr_canonicalize(info.sender.as_str())? {
+            return Err(ContractError::Unauthorized {});
+        }
+        // Palette tiles store indices, so existing entries must keep their place.
+        if let Some(old) = &config.palette {
+            match &palette {
+                Some(palette) if palette.starts_with(old) => {}
+                _ => return Err(ContractError::InvalidPalette {}),
+            }
+        }
+        config.palette = palette;
         Ok(config)
     })?;
 
-    Ok(Response::new().add_attributes(vec![("action", "update_config")]))
+    Ok(Response::new().add_attribute("action", "update_palette"))
+}
+
+pub fn execute_withdraw_fees(
+    deps: DepsMut,
+    info: MessageInfo,
+    env: Env,
+    recipient: String,
+    amount: Vec<Coin>,
+) -> Result<Response, ContractError> {
+    let config = config_read(deps.storage).load()?;
+    if config.owner != deps.api.addr_canonicalize(info.sender.as_str())? {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let recipient = deps.api.addr_validate(&recipient)?;
+
+    // The same denom may be listed more than once, so compare the totals per denom.
+    let mut totals: BTreeMap<String, Uint128> = BTreeMap::new();
+    for coin in amount {
+        if coin.amount.is_zero() {
+            return Err(ContractError::InvalidWithdrawal {});
+        }
+        *totals.entry(coin.denom).or_default() += coin.amount;
+    }
+    if totals.is_empty() {
+        return Err(ContractError::InvalidWithdrawal {});
+    }
+
+    let mut amount = Vec::with_capacity(totals.len());
+    for (denom, total) in totals {
+        let balance = deps
+            .querier
+            .query_balance(env.contract.address.clone(), denom.clone())?;
+        if total + escrowed(deps.storage, &denom)? > balance.amount {
+            return Err(ContractError::InsufficientFunds {});
+        }
+        amount.push(Coin::new(total.u128(), denom));
+    }
+
+    Ok(Response::new()
+        .add_message(BankMsg::Send {
+            to_address: recipient.to_string(),
+            amount: amount.clone(),
+        })
+        .add_attribute("action", "withdraw_fees")
+        .add_attribute("recipient", recipient)
+        .add_attribute("amount", format!("{:?}", amount)))
+}
+
+pub fn execute_transfer_nft(
+    deps: DepsMut,
+    info: MessageInfo,
+    env: Env,
+    recipient: String,
+    token_id: String,
+) -> Result<Response, ContractError> {
+    transfer_tile(deps, &env, &info, &recipient, &token_id)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "transfer_nft")
+        .add_attribute("sender", info.sender)
+        .add_attribute("recipient", recipient)
+        .add_attribute("token_id", token_id))
+}
+
+pub fn execute_send_nft(
+    deps: DepsMut,
+    info: MessageInfo,
+    env: Env,
+    contract: String,
+    token_id: String,
+    msg: Binary,
+) -> Result<Response, ContractError> {
+    transfer_tile(deps, &env, &info, &contract, &token_id)?;
+
+    let send = Cw721ReceiveMsg {
+        sender: info.sender.to_string(),
+        token_id: token_id.clone(),
+        msg,
+    };
+
+    Ok(Response::new()
+        .add_message(send.into_cosmos_msg(contract.clone())?)
+        .add_attribute("action", "send_nft")
+        .add_attribute("sender", info.sender)
+        .add_attribute("recipient", contract)
+        .add_attribute("token_id", token_id))
+}
+
+pub fn execute_approve(
+    deps: DepsMut,
+    info: MessageInfo,
+    env: Env,
+    spender: String,
+    token_id: String,
+    expires: Option<Expiration>,
+) -> Result<Response, ContractError> {
+    update_approvals(deps, &env, &info, &spender, &token_id, true, expires)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "approve")
+        .add_attribute("sender", info.sender)
+        .add_attribute("spender", spender)
+        .add_attribute("token_id", token_id))
+}
+
+pub fn execute_revoke(
+    deps: DepsMut,
+    info: MessageInfo,
+    env: Env,
+    spender: String,
+    token_id: String,
+) -> Result<Response, ContractError> {
+    update_approvals(deps, &env, &info, &spender, &token_id, false, None)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "revoke")
+        .add_attribute("sender", info.sender)
+        .add_attribute("spender", spender)
+        .add_attribute("token_id", token_id))
 }
 
 pub fn cw721_base_execute(
@@ -190,38 +1381,336 @@ pub fn cw721_base_execute(
         .map_err(|err| err.into())
 }
 
-fn token_minted(deps: Deps, env: Env, position: u16) -> bool {
-    get_owner(deps, env, position).is_some()
+/// Moves a tile to `recipient` and clears its approvals. Mirrors cw721-base, which cannot be
+/// used directly since tiles are not keyed by their string token id.
+fn transfer_tile(
+    deps: DepsMut,
+    env: &Env,
+    info: &MessageInfo,
+    recipient: &str,
+    token_id: &str,
+) -> Result<PixelTokenInfo, ContractError> {
+    let position = parse_token_id(token_id)?;
+    let mut token = tokens().load(deps.storage, U16Key::new(position))?;
+    check_can_send(deps.as_ref(), env, &info.sender, &token)?;
+
+    token.owner = deps.api.addr_validate(recipient)?;
+    token.approvals = vec![];
+    tokens().save(deps.storage, U16Key::new(position), &token)?;
+    // A listing made by the previous owner can no longer be bought.
+    listings().remove(deps.storage, U16Key::new(position));
+    Ok(token)
 }
 
-fn get_owner(deps: Deps, env: Env, position: u16) -> Option<String> {
-    let query_response = QueryHandler::cw721_base_query(
-        deps,
-        env,
-        QueryMsg::OwnerOf {
-            token_id: position.to_string(),
-            include_expired: None,
-        },
-    );
-    match query_response {
-        Ok(result) => {
-            let response: OwnerOfResponse = from_binary(&result).unwrap();
-            Some(response.owner)
+/// Removes any approval of `spender` on a tile and, if `add` is set, grants a new one.
+fn update_approvals(
+    deps: DepsMut,
+    env: &Env,
+    info: &MessageInfo,
+    spender: &str,
+    token_id: &str,
+    add: bool,
+    expires: Option<Expiration>,
+) -> Result<PixelTokenInfo, ContractError> {
+    let position = parse_token_id(token_id)?;
+    let mut token = tokens().load(deps.storage, U16Key::new(position))?;
+    check_can_approve(deps.as_ref(), env, &info.sender, &token)?;
+
+    let spender = deps.api.addr_validate(spender)?;
+    token
+        .approvals
+        .retain(|approval| approval.spender != spender);
+
+    if add {
+        let expires = check_not_expired(expires, env)?;
+        token.approvals.push(Approval { spender, expires });
+    }
+
+    tokens().save(deps.storage, U16Key::new(position), &token)?;
+    Ok(token)
+}
+
+/// The owner, any spender with a non-expired approval and non-expired operators may transfer a
+/// tile.
+fn check_can_send(
+    deps: Deps,
+    env: &Env,
+    sender: &Addr,
+    token: &PixelTokenInfo,
+) -> Result<(), ContractError> {
+    if token.owner == *sender
+        || token
+            .approvals
+            .iter()
+            .any(|approval| approval.spender == *sender && !approval.is_expired(&env.block))
+        || is_operator(deps, env, &token.owner, sender)?
+    {
+        Ok(())
+    } else {
+        Err(ContractError::Unauthorized {})
+    }
+}
+
+/// The owner and their non-expired operators may grant or revoke approvals and editors.
+fn check_can_approve(
+    deps: Deps,
+    env: &Env,
+    sender: &Addr,
+    token: &PixelTokenInfo,
+) -> Result<(), ContractError> {
+    if token.owner == *sender || is_operator(deps, env, &token.owner, sender)? {
+        Ok(())
+    } else {
+        Err(ContractError::Unauthorized {})
+    }
+}
+
+/// Records that a tile was edited in the current block, keeping `previous` as its most recent
+/// previous version and dropping the oldest version once `MAX_HISTORY` are kept.
+fn record_edit(
+    storage: &mut dyn Storage,
+    env: &Env,
+    position: u16,
+    previous: PixelExtension,
+) -> StdResult<()> {
+    history().update(storage, U16Key::new(position), |entries| -> StdResult<_> {
+        let mut entries = entries.unwrap_or_default();
+        if entries.len() >= MAX_HISTORY {
+            entries.remove(0);
+        }
+        entries.push(HistoryEntry {
+            extension: previous,
+            height: env.block.height,
+        });
+        Ok(entries)
+    })?;
+    last_edits().save(storage, U16Key::new(position), &LastEdit::from(&env.block))
+}
+
+/// Fails if the tile at `position` was edited less than `edit_cooldown` ago.
+fn check_cooldown(
+    storage: &dyn Storage,
+    config: &Config,
+    env: &Env,
+    position: u16,
+) -> Result<(), ContractError> {
+    let cooldown = match &config.edit_cooldown {
+        Some(cooldown) => cooldown,
+        None => return Ok(()),
+    };
+    if let Some(last_edit) = last_edits().may_load(storage, U16Key::new(position))? {
+        let until = last_edit.cooldown_end(cooldown);
+        if !until.is_expired(&env.block) {
+            return Err(ContractError::CooldownActive { until });
         }
-        Err(_) => None,
     }
+    Ok(())
 }
 
-fn check_sufficient_funds(funds: Vec<Coin>, required: &Coin) -> Result<(), ContractError> {
-    if required.amount.u128() == 0 {
+/// Validates pixel data given either as a color map or as encoded bytes and returns it in the
+/// encoding tiles are stored in: palette indices if the wall has a palette, RGBA bytes otherwise.
+fn encode_pixels(
+    config: &Config,
+    color_map: Option<ColorMap>,
+    pixels: Option<Binary>,
+) -> Result<Option<(Binary, PixelMode)>, ContractError> {
+    let pixels = match (color_map, pixels) {
+        (Some(_), Some(_)) => return Err(ContractError::ConflictingPixelData {}),
+        (None, None) => return Ok(None),
+        (Some(color_map), None) => {
+            if !config.is_valid_color_map(&color_map) {
+                return Err(ContractError::InvalidColorMapSize {});
+            }
+            if config.palette.is_none() {
+                return Ok(Some((encode_color_map(&color_map), PixelMode::Rgba)));
+            }
+            let indices = color_map
+                .iter()
+                .flatten()
+                .map(|color| config.palette_index(color))
+                .collect::<Option<Vec<u8>>>()
+                .ok_or(ContractError::ColorNotInPalette {})?;
+            Binary(indices)
+        }
+        (None, Some(pixels)) => pixels,
+    };
+
+    match &config.palette {
+        None if pixels.len() == config.pixels_len() => Ok(Some((pixels, PixelMode::Rgba))),
+        Some(palette) if pixels.len() == config.pixels_len() / 4 => {
+            if pixels.iter().any(|index| *index as usize >= palette.len()) {
+                return Err(ContractError::ColorNotInPalette {});
+            }
+            Ok(Some((pixels, PixelMode::Palette)))
+        }
+        _ => Err(ContractError::InvalidColorMapSize {}),
+    }
+}
+
+/// While a tile is leased only the lessee may edit it. Otherwise the owner, their operators and
+/// the editors they approved may.
+fn check_can_edit(
+    deps: Deps,
+    env: &Env,
+    sender: &Addr,
+    position: u16,
+    token: &PixelTokenInfo,
+) -> Result<(), ContractError> {
+    if let Some(lease) = leases().may_load(deps.storage, U16Key::new(position))? {
+        if lease.is_active(&env.block) {
+            return if lease.lessee == *sender {
+                Ok(())
+            } else {
+                Err(ContractError::LeaseActive {
+                    until: lease.expires,
+                })
+            };
+        }
+    }
+
+    if token.owner == *sender || is_operator(deps, env, &token.owner, sender)? {
         return Ok(());
     }
-    let sent_sufficient_funds = funds
-        .iter()
-        .any(|coin| coin.denom == required.denom && coin.amount.u128() >= required.amount.u128());
-    if sent_sufficient_funds {
-        Ok(())
+
+    let approval = editors().may_load(deps.storage, (U16Key::new(position), sender))?;
+    match approval {
+        Some(approval) if approval.is_valid(&token.owner, &env.block) => Ok(()),
+        _ => Err(ContractError::Unauthorized {}),
+    }
+}
+
+/// Returns `expires`, defaulting to never, unless it has already passed.
+fn check_not_expired(expires: Option<Expiration>, env: &Env) -> Result<Expiration, ContractError> {
+    // reject expired data as invalid
+    let expires = expires.unwrap_or_default();
+    if expires.is_expired(&env.block) {
+        return Err(ContractError::Expired {});
+    }
+    Ok(expires)
+}
+
+fn is_operator(deps: Deps, env: &Env, owner: &Addr, sender: &Addr) -> StdResult<bool> {
+    let cw721_contract = Cw721Contract::<PixelExtension, Empty>::default();
+    let expires = cw721_contract
+        .operators
+        .may_load(deps.storage, (owner, sender))?;
+    Ok(matches!(expires, Some(expires) if !expires.is_expired(&env.block)))
+}
+
+/// Checks that `funds` cover `required` and returns everything sent on top of it, i.e. the
+/// surplus of the fee denom and any coins of other denoms, so it can be refunded.
+fn check_sufficient_funds(funds: Vec<Coin>, required: &Coin) -> Result<Vec<Coin>, ContractError> {
+    let mut remaining = required.amount;
+    let mut refund = vec![];
+    for coin in funds {
+        if coin.denom == required.denom && !remaining.is_zero() {
+            if coin.amount < remaining {
+                return Err(ContractError::InsufficientFunds {});
+            }
+            let surplus = coin.amount - remaining;
+            remaining = Uint128::zero();
+            if !surplus.is_zero() {
+                refund.push(Coin {
+                    denom: coin.denom,
+                    amount: surplus,
+                });
+            }
+        } else if !coin.amount.is_zero() {
+            refund.push(coin);
+        }
+    }
+    if remaining.is_zero() {
+        Ok(refund)
     } else {
         Err(ContractError::InsufficientFunds {})
     }
 }
+
+/// Checks that the platform fee and the royalty together take at most the whole sale price, and
+/// that the royalty recipient is a valid address.
+fn check_sale_fees(deps: Deps, config: &Config) -> Result<(), ContractError> {
+    if config.platform_fee > Decimal::one() {
+        return Err(ContractError::InvalidPlatformFee {});
+    }
+    if let Some(royalty) = &config.royalty {
+        deps.api.addr_validate(royalty.recipient.as_str())?;
+        if royalty.bps > MAX_ROYALTY_BPS || config.platform_fee + royalty.share() > Decimal::one() {
+            return Err(ContractError::InvalidRoyalty {});
+        }
+    }
+    Ok(())
+}
+
+/// Returns the amount of `denom` held in escrow for the highest bids of English auctions.
+fn escrowed(storage: &dyn Storage, denom: &str) -> StdResult<Uint128> {
+    english_auctions()
+        .range(storage, None, None, Order::Ascending)
+        .try_fold(Uint128::zero(), |total, item| {
+            let (_, auction) = item?;
+            Ok(match auction.highest_bid {
+                Some(bid) if bid.amount.denom == denom => total + bid.amount.amount,
+                _ => total,
+            })
+        })
+}
+
+fn display_option<T: fmt::Display>(value: &Option<T>) -> String {
+    match value {
+        Some(value) => value.to_string(),
+        None => "none".to_string(),
+    }
+}
+
+/// Returns `count` times `fee`.
+fn fee_for(fee: &Coin, count: usize) -> StdResult<Coin> {
+    Ok(Coin {
+        denom: fee.denom.clone(),
+        amount: fee.amount.checked_mul(Uint128::from(count as u128))?,
+    })
+}
+
+/// Checks that `funds` cover the edit fee for `count` tiles, if the wall charges one. Returns
+/// the fee charged and the funds to refund, which are all of them on a free wall.
+fn charge_edit_fee(
+    config: &Config,
+    funds: Vec<Coin>,
+    count: usize,
+) -> Result<(Option<Coin>, Vec<Coin>), ContractError> {
+    match &config.edit_fee {
+        Some(edit_fee) => {
+            let fee = fee_for(edit_fee, count)?;
+            let refund = check_sufficient_funds(funds, &fee)?;
+            Ok((Some(fee), refund))
+        }
+        None => Ok((
+            None,
+            funds
+                .into_iter()
+                .filter(|coin| !coin.amount.is_zero())
+                .collect(),
+        )),
+    }
+}
+
+/// Records the edit fee charged by `charge_edit_fee` and refunds any surplus.
+fn add_edit_fee(response: Response, sender: &Addr, charged: (Option<Coin>, Vec<Coin>)) -> Response {
+    let (fee, refund) = charged;
+    let response = match fee {
+        Some(fee) => response.add_attribute("edit_fee", format!("{:?}", fee)),
+        None => response,
+    };
+    add_refund(response, sender, refund)
+}
+
+/// Appends a `BankMsg` returning `refund` to `recipient`, if there is anything to return.
+fn add_refund(response: Response, recipient: &Addr, refund: Vec<Coin>) -> Response {
+    if refund.is_empty() {
+        return response;
+    }
+    response
+        .add_attribute("refund", format!("{:?}", refund))
+        .add_message(BankMsg::Send {
+            to_address: recipient.to_string(),
+            amount: refund,
+        })
+}
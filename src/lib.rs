@@ -1,7 +1,9 @@
 pub mod contract;
 mod error;
 pub mod execute;
+pub mod migrations;
 pub mod msg;
+pub mod pricing;
 pub mod query;
 pub mod state;
 
@@ -0,0 +1,194 @@
+use cosmwasm_std::{Order, StdResult, Storage};
+use cw2::get_contract_version;
+use cw721_base::state::TokenInfo;
+use cw_storage_plus::{Bound, IndexedMap, Item, MultiIndex, U16Key};
+use schemars::JsonSchema;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::error::ContractError;
+use crate::state::{
+    encode_color_map, parse_token_id, position_from_key, token_owner_idx, tokens, ColorMap,
+    PixelExtension, PixelMode, TokenIndexes,
+};
+
+/// How many tiles a single `migrate` call migrates unless told otherwise.
+pub const DEFAULT_MIGRATION_LIMIT: u32 = 1000;
+
+/// A state migration that upgrades storage written by any contract version older than
+/// `version` into the layout expected by `version`.
+struct Migration {
+    version: &'static str,
+    /// Migrates at most the given number of tiles and returns how many it migrated. Migrating
+    /// fewer than that means the migration is complete.
+    run: fn(&mut dyn Storage, usize) -> StdResult<usize>,
+}
+
+/// The outcome of a `migrate_state` call.
+pub struct MigrationProgress {
+    /// The versions of the migrations completed by this call.
+    pub applied: Vec<&'static str>,
+    /// Whether every migration up to the target version is complete.
+    pub complete: bool,
+}
+
+/// All state migrations, ordered by the version that introduced them.
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: "0.2.0",
+        run: rekey_tokens,
+    },
+    Migration {
+        version: "0.3.0",
+        run: encode_pixels,
+    },
+];
+
+/// Tile extension written before 0.3.0, holding every pixel as a `Color` object.
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct LegacyPixelExtension {
+    pub pixel_colors: ColorMap,
+    pub url: String,
+}
+
+/// Parses a `major.minor.patch` version string, ignoring any pre-release or build suffix.
+pub fn parse_version(version: &str) -> Result<(u64, u64, u64), ContractError> {
+    let invalid = || ContractError::InvalidVersion {
+        version: version.to_string(),
+    };
+    let core = version.split(&['-', '+'][..]).next().unwrap_or("");
+    let parts = core
+        .split('.')
+        .map(|part| part.parse::<u64>().map_err(|_| invalid()))
+        .collect::<Result<Vec<u64>, ContractError>>()?;
+    match parts.as_slice() {
+        [major, minor, patch] => Ok((*major, *minor, *patch)),
+        _ => Err(invalid()),
+    }
+}
+
+/// Runs, in order, every migration introduced after `from` and up to and including `to`,
+/// migrating at most `limit` tiles in total. Stops at the first migration left incomplete.
+pub fn migrate_state(
+    storage: &mut dyn Storage,
+    from: &str,
+    to: &str,
+    limit: usize,
+) -> Result<MigrationProgress, ContractError> {
+    let from = parse_version(from)?;
+    let to = parse_version(to)?;
+
+    let mut applied = vec![];
+    let mut remaining = limit.max(1);
+    for migration in MIGRATIONS {
+        let version = parse_version(migration.version)?;
+        if version > from && version <= to {
+            let migrated = (migration.run)(storage, remaining)?;
+            if migrated == remaining {
+                return Ok(MigrationProgress {
+                    applied,
+                    complete: false,
+                });
+            }
+            remaining -= migrated;
+            applied.push(migration.version);
+        }
+    }
+    Ok(MigrationProgress {
+        applied,
+        complete: true,
+    })
+}
+
+/// Fails while the stored state is older than `version`, as happens until a batched migration
+/// completes. Tiles not yet migrated can not be read by this version.
+pub fn check_migrated(storage: &dyn Storage, version: &str) -> Result<(), ContractError> {
+    if get_contract_version(storage)?.version != version {
+        return Err(ContractError::MigrationPending {});
+    }
+    Ok(())
+}
+
+/// Token storage written before 0.2.0, keyed by the decimal token id as cw721-base does.
+pub fn legacy_tokens<'a, T>() -> IndexedMap<'a, &'a str, TokenInfo<T>, TokenIndexes<'a, T>>
+where
+    T: Serialize + DeserializeOwned + Clone,
+{
+    let indexes = TokenIndexes {
+        owner: MultiIndex::new(token_owner_idx, "tokens", "tokens__owner"),
+    };
+    IndexedMap::new("tokens", indexes)
+}
+
+/// Tile storage written by 0.2.0, in the layout of `tokens()` but with the legacy extension.
+pub fn legacy_tiles<'a>(
+) -> IndexedMap<'a, U16Key, TokenInfo<LegacyPixelExtension>, TokenIndexes<'a, LegacyPixelExtension>>
+{
+    let indexes = TokenIndexes {
+        owner: MultiIndex::new(token_owner_idx, "tiles", "tiles__owner"),
+    };
+    IndexedMap::new("tiles", indexes)
+}
+
+/// 0.2.0: moves up to `limit` tiles from `legacy_tokens()` to `legacy_tiles()`, which is keyed
+/// by the big-endian position. Moved tiles are removed, so each call continues where the last
+/// one stopped.
+fn rekey_tokens(storage: &mut dyn Storage, limit: usize) -> StdResult<usize> {
+    let legacy = legacy_tokens::<LegacyPixelExtension>();
+    let entries = legacy
+        .range(storage, None, None, Order::Ascending)
+        .take(limit)
+        .collect::<StdResult<Vec<_>>>()?;
+    let migrated = entries.len();
+    for (key, token) in entries {
+        let token_id = String::from_utf8(key)?;
+        legacy_tiles().save(storage, U16Key::new(parse_token_id(&token_id)?), &token)?;
+        legacy.remove(storage, &token_id)?;
+    }
+    Ok(migrated)
+}
+
+/// The position of the last tile re-encoded by an incomplete `encode_pixels`.
+fn encode_pixels_cursor<'a>() -> Item<'a, u16> {
+    Item::new("encode_pixels_cursor")
+}
+
+/// 0.3.0: re-encodes the color map of up to `limit` tiles in `legacy_tiles()` as RGBA bytes.
+/// Re-encoded tiles keep their key, so each call continues after the last tile of the one
+/// before.
+fn encode_pixels(storage: &mut dyn Storage, limit: usize) -> StdResult<usize> {
+    let start = encode_pixels_cursor()
+        .may_load(storage)?
+        .map(|position| Bound::exclusive(U16Key::new(position)));
+    let entries = legacy_tiles()
+        .range(storage, start, None, Order::Ascending)
+        .take(limit)
+        .collect::<StdResult<Vec<_>>>()?;
+    let migrated = entries.len();
+    let mut last = None;
+    for (key, token) in entries {
+        let position = position_from_key(&key)?;
+        last = Some(position);
+        let key = U16Key::new(position);
+        // Remove the entry first, as `tokens()` cannot read the legacy extension.
+        legacy_tiles().remove(storage, key.clone())?;
+        let extension = PixelExtension {
+            pixels: encode_color_map(&token.extension.pixel_colors),
+            mode: PixelMode::Rgba,
+            url: token.extension.url,
+        };
+        let token = TokenInfo {
+            owner: token.owner,
+            approvals: token.approvals,
+            token_uri: token.token_uri,
+            extension,
+        };
+        tokens().save(storage, key, &token)?;
+    }
+
+    match last {
+        Some(position) if migrated == limit => encode_pixels_cursor().save(storage, &position)?,
+        _ => encode_pixels_cursor().remove(storage),
+    }
+    Ok(migrated)
+}
@@ -1,5 +1,10 @@
-use crate::state::{Color, PixelExtension};
-use cosmwasm_std::{Binary, Coin};
+use crate::pricing::PricingCurve;
+use crate::state::{
+    Color, ColorMap, Config, DutchAuction, EditorApproval, HistoryEntry, Lease, LeaseOffer,
+    Listing, PixelExtension, PixelTokenInfo, PriceZone, Royalty,
+};
+use cosmwasm_std::{Binary, Coin, Decimal, Timestamp, Uint128};
+use cw0::Duration;
 use cw721::Expiration;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
@@ -9,24 +14,189 @@ use cw721_base::msg::{ExecuteMsg as CW721ExecuteMsg, QueryMsg as CW721QueryMsg};
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct InstantiateMsg {
     pub mint_fee: Coin,
+    /// Width of the wall in tiles, 200 if not given.
+    pub width: Option<u16>,
+    /// Height of the wall in tiles, 200 if not given.
+    pub height: Option<u16>,
+    /// Width and height of a tile in pixels, 5 if not given.
+    pub tile_size: Option<u8>,
+    /// Restricts painting to at most 256 colours, see `Config::palette`.
+    pub palette: Option<Vec<Color>>,
+    /// Minimum number of blocks or seconds between two edits of the same tile.
+    pub edit_cooldown: Option<Duration>,
+    /// Charged per tile for every change of pixel data, free if not given.
+    pub edit_fee: Option<Coin>,
+    /// How the price of a tile grows from `mint_fee`, flat if not given.
+    pub pricing: Option<PricingCurve>,
+    /// Share of every marketplace sale kept by the contract, nothing if not given.
+    pub platform_fee: Option<Decimal>,
+    /// Share of every secondary sale paid to a recipient, nothing if not given.
+    pub royalty: Option<Royalty>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MigrateMsg {
+    /// The most tiles to migrate in this call, `DEFAULT_MIGRATION_LIMIT` if unset. Large walls
+    /// are migrated by calling `migrate` again until the response reports it complete.
+    pub limit: Option<u32>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum ExecuteMsg {
+    /// Changes the given parameters. Only callable by the config owner.
     UpdateConfig {
+        /// No longer supported: the owner is changed with `ProposeOwner` and `AcceptOwnership`.
+        /// Setting it is rejected rather than ignored.
         owner: Option<String>,
+        mint_fee: Option<Coin>,
+        /// Sets the fee charged per edited tile. A zero amount removes the fee.
+        edit_fee: Option<Coin>,
+        /// Sets the minimum time between two edits of a tile. A zero duration removes it.
+        edit_cooldown: Option<Duration>,
+        pricing: Option<PricingCurve>,
+        platform_fee: Option<Decimal>,
+        /// Sets the royalty on secondary sales. Zero basis points remove it.
+        royalty: Option<Royalty>,
+    },
+    /// Replaces the price zones. Earlier zones take precedence over later ones they overlap.
+    /// Only callable by the config owner.
+    SetPriceZones {
+        zones: Vec<PriceZone>,
+    },
+    /// Reserves `tiles` for a Dutch auction whose price falls linearly from `start_price` at
+    /// `start` to `end_price` at `end`. Only callable by the config owner.
+    CreateAuction {
+        tiles: Vec<u16>,
+        start_price: Coin,
+        end_price: Coin,
+        start: Timestamp,
+        end: Timestamp,
+    },
+    /// Mints a tile of an active auction to the sender at the current price.
+    BidAuction {
+        auction_id: u64,
+        token_id: u16,
+    },
+    /// Reserves the tile for an English auction ending at `expires`. Only callable by the config
+    /// owner.
+    CreateEnglishAuction {
+        token_id: u16,
+        min_bid: Coin,
+        expires: Expiration,
+    },
+    /// Bids the funds sent in the auction's denom on the tile. The bid is held by the contract and
+    /// refunded once it is outbid.
+    PlaceBid {
+        token_id: u16,
+    },
+    /// Mints the tile to the highest bidder once the auction has ended, or releases it if nobody
+    /// bid. Callable by anyone.
+    SettleAuction {
+        token_id: u16,
+    },
+    /// Offers the tile for sale at `price`, replacing any previous listing. Only callable by the
+    /// owner of the tile.
+    ListTile {
+        token_id: u16,
+        price: Coin,
+    },
+    /// Buys a listed tile, paying the royalty to its recipient and the seller the price minus the
+    /// platform fee and the royalty.
+    BuyTile {
+        token_id: u16,
     },
+    /// Withdraws the tile from sale. Only callable by the seller.
+    CancelListing {
+        token_id: u16,
+    },
+    /// Offers the drawing rights of the tile for `duration` at `price`, replacing any previous
+    /// offer. Only callable by the owner of the tile.
+    OfferLease {
+        token_id: u16,
+        price: Coin,
+        duration: Duration,
+    },
+    /// Rents the tile as offered, paying its owner. Until the lease expires only the sender may
+    /// change the tile's pixel data.
+    AcceptLease {
+        token_id: u16,
+    },
+    /// Withdraws the open lease offer for the tile. Only callable by the owner of the tile.
+    CancelLeaseOffer {
+        token_id: u16,
+    },
+    /// Proposes a new config owner. Only callable by the config owner.
+    ProposeOwner {
+        owner: String,
+    },
+    /// Makes the sender the config owner. Only callable by the proposed owner.
+    AcceptOwnership {},
+    /// Withdraws the pending ownership proposal. Only callable by the config owner.
+    CancelOwnershipProposal {},
+    /// Sets the palette or appends colours to it. Once set, the palette can not be removed and
+    /// its existing entries can not be changed. Only callable by the config owner.
+    UpdatePalette {
+        palette: Option<Vec<Color>>,
+    },
+    /// Pixel data may be given either as a `color_map` or, more compactly, as `pixels`
+    /// holding 4 RGBA bytes per pixel, row by row. If the wall has a palette, `pixels` instead
+    /// holds one palette index per pixel and every colour of `color_map` must be in the palette.
     Mint {
         token_id: u16,
-        color_map: Option<[[Color; 5]; 5]>,
+        color_map: Option<ColorMap>,
+        pixels: Option<Binary>,
         url: Option<String>,
     },
+    /// Mints several tiles in one transaction, charging the current mint price of each tile.
+    BatchMint {
+        tiles: Vec<MintTile>,
+    },
+    /// Mints every tile in the rectangle of `width` x `height` tiles whose top left tile is at
+    /// (`x`, `y`). `color_maps`, if given, holds one color map per tile in row-major order.
+    MintRegion {
+        x: u16,
+        y: u16,
+        width: u16,
+        height: u16,
+        color_maps: Option<Vec<ColorMap>>,
+    },
     ChangePixelData {
         token_id: u16,
-        color_map: Option<[[Color; 5]; 5]>,
+        color_map: Option<ColorMap>,
+        pixels: Option<Binary>,
         url: Option<String>,
     },
+    /// Changes the pixel data of several tiles owned by the sender in one transaction.
+    BatchChangePixelData {
+        changes: Vec<PixelDataChange>,
+    },
+    /// Restores the pixel data a tile had before its most recent change.
+    UndoPixelChange {
+        token_id: u16,
+    },
+    /// Changes individual pixels of a tile, leaving the others untouched.
+    SetPixels {
+        token_id: u16,
+        pixels: Vec<PixelUpdate>,
+    },
+    /// Allows `editor` to change the pixel data of a tile until `expires`, without granting
+    /// transfer rights.
+    ApproveEditor {
+        token_id: u16,
+        editor: String,
+        expires: Option<Expiration>,
+    },
+    RevokeEditor {
+        token_id: u16,
+        editor: String,
+    },
+    /// Sends accumulated mint fees held by the contract to `recipient`. Bids held in escrow for
+    /// English auctions can not be withdrawn. Only callable by the config owner.
+    WithdrawFees {
+        recipient: String,
+        amount: Vec<Coin>,
+    },
 
     /// BELOW ARE COPIED FROM CW721-BASE
     TransferNft {
@@ -56,6 +226,53 @@ pub enum ExecuteMsg {
     },
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MintTile {
+    pub token_id: u16,
+    pub color_map: Option<ColorMap>,
+    pub pixels: Option<Binary>,
+    pub url: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PixelDataChange {
+    pub token_id: u16,
+    pub color_map: Option<ColorMap>,
+    pub pixels: Option<Binary>,
+    pub url: Option<String>,
+}
+
+/// A single pixel of a tile, in pixel coordinates within the tile.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PixelUpdate {
+    pub x: u8,
+    pub y: u8,
+    pub color: Color,
+}
+
+/// A rectangle of tiles on the wall, in tile coordinates.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Region {
+    pub x: u16,
+    pub y: u16,
+    pub width: u16,
+    pub height: u16,
+}
+
+impl Region {
+    pub fn is_on_wall(&self, config: &Config) -> bool {
+        self.x as u32 + self.width as u32 <= config.width as u32
+            && self.y as u32 + self.height as u32 <= config.height as u32
+    }
+
+    /// Returns the positions of all tiles inside the region in row-major order.
+    pub fn positions(&self, config: &Config) -> Vec<u16> {
+        (self.y..self.y + self.height)
+            .flat_map(|y| (self.x..self.x + self.width).map(move |x| config.position(x, y)))
+            .collect()
+    }
+}
+
 impl From<ExecuteMsg> for CW721ExecuteMsg<PixelExtension> {
     fn from(msg: ExecuteMsg) -> CW721ExecuteMsg<PixelExtension> {
         match msg {
@@ -103,6 +320,71 @@ pub enum QueryMsg {
     PixelInfo {
         token_id: String,
     },
+    /// Returns minted tiles with their pixel data, ordered by position.
+    PixelInfos {
+        start_after: Option<u16>,
+        limit: Option<u32>,
+    },
+    /// Returns the tiles owned by `owner` with their pixel data, ordered by position.
+    PixelInfosByOwner {
+        owner: String,
+        start_after: Option<u16>,
+        limit: Option<u32>,
+    },
+    /// Returns the editors currently allowed to change the pixel data of a tile.
+    Editors {
+        token_id: u16,
+    },
+    /// Returns the pixels of a rectangle of tiles as packed RGBA bytes, row by row. Unminted
+    /// tiles are transparent.
+    Canvas {
+        x: u16,
+        y: u16,
+        width: u16,
+        height: u16,
+    },
+    /// Returns the price of minting the tile with the next mint.
+    MintPrice {
+        token_id: u16,
+    },
+    /// Returns the Dutch auctions currently accepting bids, ordered by id.
+    Auctions {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Returns the English auction of a tile that has not been settled yet.
+    EnglishAuction {
+        token_id: u16,
+    },
+    /// Returns the tiles for sale, ordered by token id.
+    Listings {
+        start_after: Option<u16>,
+        limit: Option<u32>,
+    },
+    /// Returns the open lease offer and the active lease of a tile, if any.
+    Lease {
+        token_id: u16,
+    },
+    /// Returns the price zones, in order of precedence.
+    PriceZones {},
+    /// Returns the previous versions of a tile's pixel data, most recent first.
+    PixelHistory {
+        token_id: u16,
+        limit: Option<u32>,
+    },
+    /// Returns the colours of a tile, resolving palette indices.
+    ColorMap {
+        token_id: u16,
+    },
+    /// Returns all coins currently held by the contract.
+    Balance {},
+    /// Returns the royalty owed on a sale of the tile for `sale_price`, as defined by cw2981.
+    RoyaltyInfo {
+        token_id: String,
+        sale_price: Uint128,
+    },
+    /// Returns whether the contract implements cw2981 royalties.
+    CheckRoyalties {},
     /// BELOW ARE COPIED FROM CW721-BASE
     OwnerOf {
         token_id: String,
@@ -134,6 +416,98 @@ pub enum QueryMsg {
     },
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TileInfo {
+    pub token_id: u16,
+    pub info: PixelTokenInfo,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PixelInfosResponse {
+    pub tiles: Vec<TileInfo>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct EditorsResponse {
+    pub editors: Vec<EditorApproval>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CanvasResponse {
+    /// Width of the canvas in pixels.
+    pub width: u32,
+    /// Height of the canvas in pixels.
+    pub height: u32,
+    pub pixels: Binary,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MintPriceResponse {
+    pub price: Coin,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AuctionInfo {
+    pub id: u64,
+    pub auction: DutchAuction,
+    /// Current price of a tile.
+    pub price: Coin,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AuctionsResponse {
+    pub auctions: Vec<AuctionInfo>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ListingInfo {
+    pub token_id: u16,
+    pub listing: Listing,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ListingsResponse {
+    pub listings: Vec<ListingInfo>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LeaseResponse {
+    pub offer: Option<LeaseOffer>,
+    pub lease: Option<Lease>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PriceZonesResponse {
+    pub zones: Vec<PriceZone>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PixelHistoryResponse {
+    pub entries: Vec<HistoryEntry>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ColorMapResponse {
+    pub color_map: ColorMap,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RoyaltiesInfoResponse {
+    /// Empty if the wall charges no royalty.
+    pub address: String,
+    pub royalty_amount: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CheckRoyaltiesResponse {
+    pub royalty_payments: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BalanceResponse {
+    pub amount: Vec<Coin>,
+}
+
 impl From<QueryMsg> for CW721QueryMsg {
     fn from(msg: QueryMsg) -> CW721QueryMsg {
         match msg {
@@ -0,0 +1,101 @@
+use std::convert::TryFrom;
+use std::fmt;
+
+use cosmwasm_std::{Coin, Decimal, Fraction, StdResult, Uint128, Uint256};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::state::{Config, PriceZone};
+
+/// How the price of a tile depends on the number of tiles minted before it.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PricingCurve {
+    /// Every tile costs `mint_fee`.
+    Flat,
+    /// The price starts at `mint_fee` and grows by `increment` with every tile minted.
+    Linear { increment: Uint128 },
+    /// The price starts at `mint_fee` and grows by the fraction `rate` with every tile minted.
+    Exponential { rate: Decimal },
+}
+
+impl Default for PricingCurve {
+    /// Walls instantiated before pricing curves existed charge a flat fee.
+    fn default() -> Self {
+        PricingCurve::Flat
+    }
+}
+
+impl fmt::Display for PricingCurve {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PricingCurve::Flat => write!(f, "flat"),
+            PricingCurve::Linear { increment } => write!(f, "linear: {}", increment),
+            PricingCurve::Exponential { rate } => write!(f, "exponential: {}", rate),
+        }
+    }
+}
+
+/// Returns the price of the tile at `position` when minted once `minted` tiles exist. The curve
+/// starts at the price of the first zone containing the tile, or at `mint_fee` outside all zones.
+pub fn mint_price(
+    config: &Config,
+    zones: &[PriceZone],
+    position: u16,
+    minted: u64,
+) -> StdResult<Coin> {
+    let base = zones
+        .iter()
+        .find(|zone| zone.contains(config, position))
+        .map_or(config.mint_fee.amount, |zone| zone.price.amount);
+    let amount = match &config.pricing {
+        PricingCurve::Flat => base,
+        PricingCurve::Linear { increment } => {
+            base.checked_add(increment.checked_mul(Uint128::from(minted))?)?
+        }
+        PricingCurve::Exponential { rate } => {
+            let one = Uint256::from(rate.denominator());
+            let factor = one.checked_add(Uint256::from(rate.numerator()))?;
+            let amount = Uint256::from(base).checked_mul(pow_fraction(factor, one, minted)?)? / one;
+            Uint128::try_from(amount)?
+        }
+    };
+    Ok(Coin {
+        denom: config.mint_fee.denom.clone(),
+        amount,
+    })
+}
+
+/// Returns the total price of minting the tiles at `positions`, in order, once `minted` tiles
+/// exist.
+pub fn total_mint_price(
+    config: &Config,
+    zones: &[PriceZone],
+    positions: &[u16],
+    minted: u64,
+) -> StdResult<Coin> {
+    let mut total = Uint128::zero();
+    for (n, position) in (minted..).zip(positions) {
+        total = total.checked_add(mint_price(config, zones, *position, n)?.amount)?;
+    }
+    Ok(Coin {
+        denom: config.mint_fee.denom.clone(),
+        amount: total,
+    })
+}
+
+/// Raises the fixed point number `factor / one` to the power of `exp` by repeated squaring,
+/// returning the result as a multiple of `one`.
+fn pow_fraction(mut factor: Uint256, one: Uint256, mut exp: u64) -> StdResult<Uint256> {
+    let mut result = one;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = result.checked_mul(factor)? / one;
+        }
+        exp >>= 1;
+        if exp > 0 {
+            factor = factor.checked_mul(factor)? / one;
+        }
+    }
+    Ok(result)
+}
@@ -1,18 +1,386 @@
-use cosmwasm_std::{Binary, Deps, Empty, Env, StdResult};
+use cosmwasm_std::{
+    Binary, BlockInfo, Deps, Empty, Env, Order, Pair, StdError, StdResult, Uint128,
+};
+use cw721::{AllNftInfoResponse, NftInfoResponse, OwnerOfResponse, TokensResponse};
 use cw721_base::Cw721Contract;
+use cw_storage_plus::{Bound, U16Key, U64Key};
 
-use crate::msg::QueryMsg;
-use crate::state::{config_read, tokens, Config, PixelExtension, PixelTokenInfo};
+use crate::msg::{
+    AuctionInfo, AuctionsResponse, BalanceResponse, CanvasResponse, CheckRoyaltiesResponse,
+    ColorMapResponse, EditorsResponse, LeaseResponse, ListingInfo, ListingsResponse,
+    MintPriceResponse, PixelHistoryResponse, PixelInfosResponse, PriceZonesResponse, QueryMsg,
+    Region, RoyaltiesInfoResponse, TileInfo,
+};
+use crate::pricing::mint_price;
+use crate::state::{
+    auction_id_from_key, config_read, decode_color_map, dutch_auctions, editors, english_auctions,
+    history, lease_offers, leases, listings, parse_token_id, position_from_key, price_zones,
+    tokens, Config, EnglishAuction, PixelExtension, PixelTokenInfo,
+};
 
 pub fn query_config(deps: Deps) -> StdResult<Config> {
     config_read(deps.storage).load()
 }
 
 pub fn query_pixel_nft_info(deps: Deps, token_id: String) -> StdResult<PixelTokenInfo> {
-    let token = tokens().load(deps.storage, &token_id)?;
+    let token = tokens().load(deps.storage, U16Key::new(parse_token_id(&token_id)?))?;
     Ok(token)
 }
 
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
+
+pub fn query_pixel_infos(
+    deps: Deps,
+    start_after: Option<u16>,
+    limit: Option<u32>,
+) -> StdResult<PixelInfosResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(|position| Bound::exclusive(U16Key::new(position)));
+
+    let tiles = tokens()
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(parse_tile_info)
+        .collect::<StdResult<Vec<TileInfo>>>()?;
+    Ok(PixelInfosResponse { tiles })
+}
+
+pub fn query_pixel_infos_by_owner(
+    deps: Deps,
+    owner: String,
+    start_after: Option<u16>,
+    limit: Option<u32>,
+) -> StdResult<PixelInfosResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(|position| Bound::exclusive(U16Key::new(position)));
+
+    let owner = deps.api.addr_validate(&owner)?;
+    let tiles = tokens()
+        .idx
+        .owner
+        .prefix(owner)
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(parse_tile_info)
+        .collect::<StdResult<Vec<TileInfo>>>()?;
+    Ok(PixelInfosResponse { tiles })
+}
+
+fn parse_tile_info(item: StdResult<Pair<PixelTokenInfo>>) -> StdResult<TileInfo> {
+    item.and_then(|(key, info)| {
+        Ok(TileInfo {
+            token_id: position_from_key(&key)?,
+            info,
+        })
+    })
+}
+
+pub fn query_owner_of(
+    deps: Deps,
+    env: Env,
+    token_id: String,
+    include_expired: bool,
+) -> StdResult<OwnerOfResponse> {
+    let info = query_pixel_nft_info(deps, token_id)?;
+    Ok(OwnerOfResponse {
+        owner: info.owner.to_string(),
+        approvals: humanize_approvals(&env.block, &info, include_expired),
+    })
+}
+
+pub fn query_nft_info(deps: Deps, token_id: String) -> StdResult<NftInfoResponse<PixelExtension>> {
+    let info = query_pixel_nft_info(deps, token_id)?;
+    Ok(NftInfoResponse {
+        token_uri: info.token_uri,
+        extension: info.extension,
+    })
+}
+
+pub fn query_all_nft_info(
+    deps: Deps,
+    env: Env,
+    token_id: String,
+    include_expired: bool,
+) -> StdResult<AllNftInfoResponse<PixelExtension>> {
+    let info = query_pixel_nft_info(deps, token_id)?;
+    Ok(AllNftInfoResponse {
+        access: OwnerOfResponse {
+            owner: info.owner.to_string(),
+            approvals: humanize_approvals(&env.block, &info, include_expired),
+        },
+        info: NftInfoResponse {
+            token_uri: info.token_uri,
+            extension: info.extension,
+        },
+    })
+}
+
+pub fn query_tokens(
+    deps: Deps,
+    owner: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<TokensResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = match start_after {
+        Some(token_id) => Some(Bound::exclusive(U16Key::new(parse_token_id(&token_id)?))),
+        None => None,
+    };
+
+    let owner = deps.api.addr_validate(&owner)?;
+    let tokens = tokens()
+        .idx
+        .owner
+        .prefix(owner)
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|key| position_from_key(&key).map(|position| position.to_string()))
+        .collect::<StdResult<Vec<String>>>()?;
+    Ok(TokensResponse { tokens })
+}
+
+pub fn query_all_tokens(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<TokensResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = match start_after {
+        Some(token_id) => Some(Bound::exclusive(U16Key::new(parse_token_id(&token_id)?))),
+        None => None,
+    };
+
+    let tokens = tokens()
+        .sub_prefix(())
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|key| position_from_key(&key).map(|position| position.to_string()))
+        .collect::<StdResult<Vec<String>>>()?;
+    Ok(TokensResponse { tokens })
+}
+
+fn humanize_approvals(
+    block: &BlockInfo,
+    info: &PixelTokenInfo,
+    include_expired: bool,
+) -> Vec<cw721::Approval> {
+    info.approvals
+        .iter()
+        .filter(|approval| include_expired || !approval.is_expired(block))
+        .map(|approval| cw721::Approval {
+            spender: approval.spender.to_string(),
+            expires: approval.expires,
+        })
+        .collect()
+}
+
+pub fn query_editors(deps: Deps, env: Env, token_id: u16) -> StdResult<EditorsResponse> {
+    let token = tokens().load(deps.storage, U16Key::new(token_id))?;
+    let editors = editors()
+        .prefix(U16Key::new(token_id))
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(_, approval)| approval))
+        .collect::<StdResult<Vec<_>>>()?
+        .into_iter()
+        .filter(|approval| approval.is_valid(&token.owner, &env.block))
+        .collect();
+    Ok(EditorsResponse { editors })
+}
+
+/// Largest number of pixels a single canvas query may cover.
+const MAX_CANVAS_PIXELS: u32 = 62_500;
+
+pub fn query_canvas(deps: Deps, region: Region) -> StdResult<CanvasResponse> {
+    let config = config_read(deps.storage).load()?;
+    if region.width == 0 || region.height == 0 || !region.is_on_wall(&config) {
+        return Err(StdError::generic_err("Invalid canvas region"));
+    }
+    let tile_size = config.tile_size as usize;
+    let width = region.width as u32 * tile_size as u32;
+    let height = region.height as u32 * tile_size as u32;
+    if width * height > MAX_CANVAS_PIXELS {
+        return Err(StdError::generic_err(format!(
+            "Canvas may cover at most {} pixels",
+            MAX_CANVAS_PIXELS
+        )));
+    }
+
+    let row_len = width as usize * 4;
+    let mut pixels = vec![0u8; row_len * height as usize];
+    for tile_y in 0..region.height {
+        let first = config.position(region.x, region.y + tile_y);
+        // A row ending at the last tile of a 65536 tile wall ends past `u16::MAX`.
+        let end = first as u32 + region.width as u32;
+        let end = if end > u16::MAX as u32 {
+            None
+        } else {
+            Some(Bound::exclusive(U16Key::new(end as u16)))
+        };
+        let tiles = tokens().range(
+            deps.storage,
+            Some(Bound::inclusive(U16Key::new(first))),
+            end,
+            Order::Ascending,
+        );
+        for item in tiles {
+            let (key, token) = item?;
+            let tile_x = (position_from_key(&key)? - first) as usize;
+            let rgba = token.extension.to_rgba(config.palette.as_deref());
+            for (row, colors) in rgba.chunks(tile_size * 4).enumerate() {
+                let start = (tile_y as usize * tile_size + row) * row_len + tile_x * tile_size * 4;
+                pixels[start..start + colors.len()].copy_from_slice(colors);
+            }
+        }
+    }
+
+    Ok(CanvasResponse {
+        width,
+        height,
+        pixels: Binary(pixels),
+    })
+}
+
+pub fn query_mint_price(deps: Deps, token_id: u16) -> StdResult<MintPriceResponse> {
+    let config = config_read(deps.storage).load()?;
+    if !config.is_valid_position(token_id) {
+        return Err(StdError::generic_err(format!(
+            "Invalid token id: {}",
+            token_id
+        )));
+    }
+    let cw721_contract = Cw721Contract::<PixelExtension, Empty>::default();
+    let zones = price_zones().may_load(deps.storage)?.unwrap_or_default();
+    let minted = cw721_contract.token_count(deps.storage)?;
+    let price = mint_price(&config, &zones, token_id, minted)?;
+    Ok(MintPriceResponse { price })
+}
+
+pub fn query_english_auction(deps: Deps, token_id: u16) -> StdResult<EnglishAuction> {
+    english_auctions().load(deps.storage, U16Key::new(token_id))
+}
+
+pub fn query_royalty_info(
+    deps: Deps,
+    token_id: String,
+    sale_price: Uint128,
+) -> StdResult<RoyaltiesInfoResponse> {
+    tokens().load(deps.storage, U16Key::new(parse_token_id(&token_id)?))?;
+    let config = config_read(deps.storage).load()?;
+    Ok(match config.royalty {
+        Some(royalty) => RoyaltiesInfoResponse {
+            address: royalty.recipient.to_string(),
+            royalty_amount: royalty.amount(sale_price),
+        },
+        None => RoyaltiesInfoResponse {
+            address: String::new(),
+            royalty_amount: Uint128::zero(),
+        },
+    })
+}
+
+pub fn query_check_royalties() -> CheckRoyaltiesResponse {
+    CheckRoyaltiesResponse {
+        royalty_payments: true,
+    }
+}
+
+pub fn query_lease(deps: Deps, env: Env, token_id: u16) -> StdResult<LeaseResponse> {
+    let token = tokens().load(deps.storage, U16Key::new(token_id))?;
+    let offer = lease_offers()
+        .may_load(deps.storage, U16Key::new(token_id))?
+        .filter(|offer| offer.lessor == token.owner);
+    let lease = leases()
+        .may_load(deps.storage, U16Key::new(token_id))?
+        .filter(|lease| lease.is_active(&env.block));
+    Ok(LeaseResponse { offer, lease })
+}
+
+pub fn query_listings(
+    deps: Deps,
+    start_after: Option<u16>,
+    limit: Option<u32>,
+) -> StdResult<ListingsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(|token_id| Bound::exclusive(U16Key::new(token_id)));
+
+    let listings = listings()
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let (key, listing) = item?;
+            Ok(ListingInfo {
+                token_id: position_from_key(&key)?,
+                listing,
+            })
+        })
+        .collect::<StdResult<_>>()?;
+    Ok(ListingsResponse { listings })
+}
+
+pub fn query_auctions(
+    deps: Deps,
+    env: Env,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<AuctionsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(|id| Bound::exclusive(U64Key::new(id)));
+
+    let auctions = dutch_auctions()
+        .range(deps.storage, start, None, Order::Ascending)
+        .map(|item| {
+            let (key, auction) = item?;
+            Ok(AuctionInfo {
+                id: auction_id_from_key(&key)?,
+                price: auction.price(env.block.time),
+                auction,
+            })
+        })
+        .filter(|item: &StdResult<AuctionInfo>| match item {
+            Ok(info) => info.auction.is_active(&env.block),
+            Err(_) => true,
+        })
+        .take(limit)
+        .collect::<StdResult<_>>()?;
+    Ok(AuctionsResponse { auctions })
+}
+
+pub fn query_price_zones(deps: Deps) -> StdResult<PriceZonesResponse> {
+    let zones = price_zones().may_load(deps.storage)?.unwrap_or_default();
+    Ok(PriceZonesResponse { zones })
+}
+
+pub fn query_pixel_history(
+    deps: Deps,
+    token_id: u16,
+    limit: Option<u32>,
+) -> StdResult<PixelHistoryResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let entries = history()
+        .may_load(deps.storage, U16Key::new(token_id))?
+        .unwrap_or_default()
+        .into_iter()
+        .rev()
+        .take(limit)
+        .collect();
+    Ok(PixelHistoryResponse { entries })
+}
+
+pub fn query_color_map(deps: Deps, token_id: u16) -> StdResult<ColorMapResponse> {
+    let config = config_read(deps.storage).load()?;
+    let token = tokens().load(deps.storage, U16Key::new(token_id))?;
+    let rgba = token.extension.to_rgba(config.palette.as_deref());
+    Ok(ColorMapResponse {
+        color_map: decode_color_map(&rgba, config.tile_size),
+    })
+}
+
+pub fn query_balance(deps: Deps, env: Env) -> StdResult<BalanceResponse> {
+    let amount = deps.querier.query_all_balances(env.contract.address)?;
+    Ok(BalanceResponse { amount })
+}
+
 pub fn cw721_base_query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     let cw721_contract = Cw721Contract::<PixelExtension, Empty>::default();
     cw721_contract.query(deps, env, msg.into())
@@ -1,23 +1,183 @@
-use cosmwasm_std::{Addr, CanonicalAddr, Coin, Storage};
+use cosmwasm_std::{
+    Addr, Binary, BlockInfo, CanonicalAddr, Coin, Decimal, StdError, StdResult, Storage, Timestamp,
+    Uint128,
+};
 use cosmwasm_storage::{singleton, singleton_read, ReadonlySingleton, Singleton};
+use cw0::Duration;
+use cw721::Expiration;
 use cw721_base::state::TokenInfo;
-use cw_storage_plus::{Index, IndexList, IndexedMap, MultiIndex};
+use cw_storage_plus::{Index, IndexList, IndexedMap, Item, Map, MultiIndex, U16Key, U64Key};
 use schemars::JsonSchema;
+use serde::de::DeserializeOwned;
+
+use crate::pricing::PricingCurve;
 use serde::{Deserialize, Serialize};
+use std::convert::TryInto;
+use std::fmt;
 
 static KEY_CONFIG: &[u8] = b"config";
 
+/// Geometry of walls instantiated before it became configurable.
+pub const DEFAULT_WALL_WIDTH: u16 = 200;
+pub const DEFAULT_WALL_HEIGHT: u16 = 200;
+pub const DEFAULT_TILE_SIZE: u8 = 5;
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Config {
     pub owner: CanonicalAddr,
     pub mint_fee: Coin,
+    /// The wall is a grid of `width` x `height` tiles, numbered row by row.
+    #[serde(default = "default_wall_width")]
+    pub width: u16,
+    #[serde(default = "default_wall_height")]
+    pub height: u16,
+    /// Every tile is a square of `tile_size` x `tile_size` pixels.
+    #[serde(default = "default_tile_size")]
+    pub tile_size: u8,
+    /// If set, tiles painted from now on store indices into this palette instead of colours.
+    #[serde(default)]
+    pub palette: Option<Vec<Color>>,
+    /// Minimum number of blocks or seconds between two edits of the same tile.
+    #[serde(default)]
+    pub edit_cooldown: Option<Duration>,
+    /// Charged per tile for every change of pixel data, if set.
+    #[serde(default)]
+    pub edit_fee: Option<Coin>,
+    /// How the price of a tile grows from `mint_fee` as tiles are minted.
+    #[serde(default)]
+    pub pricing: PricingCurve,
+    /// Proposed new owner, who becomes `owner` once they accept.
+    #[serde(default)]
+    pub pending_owner: Option<CanonicalAddr>,
+    /// Share of every marketplace sale kept by the contract.
+    #[serde(default)]
+    pub platform_fee: Decimal,
+    /// Share of every secondary sale paid to the royalty recipient, if set.
+    #[serde(default)]
+    pub royalty: Option<Royalty>,
+}
+
+fn default_wall_width() -> u16 {
+    DEFAULT_WALL_WIDTH
+}
+
+fn default_wall_height() -> u16 {
+    DEFAULT_WALL_HEIGHT
+}
+
+fn default_tile_size() -> u8 {
+    DEFAULT_TILE_SIZE
+}
+
+impl Config {
+    pub fn tile_count(&self) -> u32 {
+        self.width as u32 * self.height as u32
+    }
+
+    pub fn is_valid_position(&self, position: u16) -> bool {
+        (position as u32) < self.tile_count()
+    }
+
+    /// Returns the position of the tile in column `x` of row `y`.
+    pub fn position(&self, x: u16, y: u16) -> u16 {
+        (y as u32 * self.width as u32 + x as u32) as u16
+    }
+
+    pub fn is_valid_color_map(&self, color_map: &[Vec<Color>]) -> bool {
+        let size = self.tile_size as usize;
+        color_map.len() == size && color_map.iter().all(|row| row.len() == size)
+    }
+
+    /// Number of bytes in the RGBA encoding of a tile.
+    pub fn pixels_len(&self) -> usize {
+        self.tile_size as usize * self.tile_size as usize * 4
+    }
+
+    /// Fully transparent pixels for a tile.
+    pub fn empty_pixels(&self) -> Binary {
+        Binary(vec![0; self.pixels_len()])
+    }
+
+    /// Returns the index of `color` in the palette, if there is one and it holds the colour.
+    pub fn palette_index(&self, color: &Color) -> Option<u8> {
+        self.palette
+            .as_ref()?
+            .iter()
+            .position(|entry| entry == color)
+            .map(|index| index as u8)
+    }
+}
+
+/// Royalties are given in basis points, i.e. hundredths of a percent.
+pub const MAX_ROYALTY_BPS: u16 = 10_000;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Royalty {
+    pub recipient: Addr,
+    pub bps: u16,
+}
+
+impl Royalty {
+    pub fn share(&self) -> Decimal {
+        Decimal::from_ratio(self.bps, MAX_ROYALTY_BPS)
+    }
+
+    /// Returns the royalty owed on a sale for `price`.
+    pub fn amount(&self, price: Uint128) -> Uint128 {
+        price.multiply_ratio(self.bps, MAX_ROYALTY_BPS)
+    }
+}
+
+impl fmt::Display for Royalty {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} bps to {}", self.bps, self.recipient)
+    }
+}
+
+/// Palettes are indexed by a single byte.
+pub const MAX_PALETTE_SIZE: usize = 256;
+
+pub fn is_valid_palette(palette: &[Color]) -> bool {
+    !palette.is_empty() && palette.len() <= MAX_PALETTE_SIZE
+}
+
+/// A rectangle of tiles, in tile coordinates, whose tiles start at their own price instead of
+/// `mint_fee`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PriceZone {
+    pub x: u16,
+    pub y: u16,
+    pub width: u16,
+    pub height: u16,
+    pub price: Coin,
+}
+
+impl PriceZone {
+    pub fn is_valid(&self, config: &Config) -> bool {
+        self.width > 0
+            && self.height > 0
+            && self.x as u32 + self.width as u32 <= config.width as u32
+            && self.y as u32 + self.height as u32 <= config.height as u32
+            && self.price.denom == config.mint_fee.denom
+    }
+
+    pub fn contains(&self, config: &Config, position: u16) -> bool {
+        let x = position % config.width;
+        let y = position / config.width;
+        x >= self.x && x - self.x < self.width && y >= self.y && y - self.y < self.height
+    }
 }
 
-pub fn config_store(storage: &mut dyn Storage) -> Singleton<Config> {
+/// Price zones, where earlier zones take precedence over later ones they overlap.
+pub fn price_zones<'a>() -> Item<'a, Vec<PriceZone>> {
+    Item::new("price_zones")
+}
+
+pub fn config_store(storage: &mut dyn Storage) -> Singleton<'_, Config> {
     singleton(storage, KEY_CONFIG)
 }
 
-pub fn config_read(storage: &dyn Storage) -> ReadonlySingleton<Config> {
+pub fn config_read(storage: &dyn Storage) -> ReadonlySingleton<'_, Config> {
     singleton_read(storage, KEY_CONFIG)
 }
 
@@ -34,33 +194,328 @@ pub struct Color {
     pub a: u8,
 }
 
-// wrap an array of colours and a url to make up for the 5x5 pixel square
+impl Color {
+    pub fn to_rgba(self) -> [u8; 4] {
+        [self.r, self.g, self.b, self.a]
+    }
+
+    pub fn from_rgba(rgba: [u8; 4]) -> Self {
+        let [r, g, b, a] = rgba;
+        Color { r, g, b, a }
+    }
+}
+
+/// The colours of a tile's pixels, row by row.
+pub type ColorMap = Vec<Vec<Color>>;
+
+/// Packs a color map into 4 RGBA bytes per pixel, row by row.
+pub fn encode_color_map(color_map: &[Vec<Color>]) -> Binary {
+    let mut pixels = Vec::with_capacity(color_map.iter().map(|row| row.len() * 4).sum());
+    for color in color_map.iter().flatten() {
+        pixels.extend_from_slice(&color.to_rgba());
+    }
+    Binary(pixels)
+}
+
+/// Unpacks RGBA bytes into rows of `tile_size` colours.
+pub fn decode_color_map(pixels: &[u8], tile_size: u8) -> ColorMap {
+    pixels
+        .chunks_exact(4)
+        .map(|rgba| Color::from_rgba([rgba[0], rgba[1], rgba[2], rgba[3]]))
+        .collect::<Vec<Color>>()
+        .chunks(tile_size as usize)
+        .map(|row| row.to_vec())
+        .collect()
+}
+
+/// How the pixels of a tile are encoded.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum PixelMode {
+    /// 4 RGBA bytes per pixel.
+    Rgba,
+    /// One byte per pixel, indexing the palette in `Config`.
+    Palette,
+}
+
+impl Default for PixelMode {
+    /// Tiles stored before palettes existed hold RGBA bytes.
+    fn default() -> Self {
+        PixelMode::Rgba
+    }
+}
+
+// wrap the colours and a url to make up for the tile_size x tile_size pixel square
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
 pub struct PixelExtension {
-    pub pixel_colors: [[Color; 5]; 5],
+    /// The tile's pixels, row by row, encoded as given by `mode`.
+    pub pixels: Binary,
+    #[serde(default)]
+    pub mode: PixelMode,
     pub url: String,
 }
 
+impl PixelExtension {
+    /// Returns the RGBA bytes of the tile's pixels, resolving palette indices against `palette`.
+    /// Indices missing from the palette resolve to a transparent pixel.
+    pub fn to_rgba(&self, palette: Option<&[Color]>) -> Vec<u8> {
+        match self.mode {
+            PixelMode::Rgba => self.pixels.to_vec(),
+            PixelMode::Palette => {
+                let mut rgba = Vec::with_capacity(self.pixels.len() * 4);
+                for index in self.pixels.iter() {
+                    rgba.extend_from_slice(
+                        &palette
+                            .and_then(|palette| palette.get(*index as usize))
+                            .map_or([0; 4], |color| color.to_rgba()),
+                    );
+                }
+                rgba
+            }
+        }
+    }
+}
+
 pub type PixelTokenInfo = TokenInfo<PixelExtension>;
 
-pub struct TokenIndexes<'a> {
-    pub owner: MultiIndex<'a, (Addr, Vec<u8>), PixelTokenInfo>,
+pub struct TokenIndexes<'a, T>
+where
+    T: Serialize + DeserializeOwned + Clone,
+{
+    pub owner: MultiIndex<'a, (Addr, Vec<u8>), TokenInfo<T>>,
 }
 
-impl<'a> IndexList<PixelTokenInfo> for TokenIndexes<'a> {
-    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<PixelTokenInfo>> + '_> {
-        let v: Vec<&dyn Index<PixelTokenInfo>> = vec![&self.owner];
+impl<'a, T> IndexList<TokenInfo<T>> for TokenIndexes<'a, T>
+where
+    T: Serialize + DeserializeOwned + Clone,
+{
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<TokenInfo<T>>> + '_> {
+        let v: Vec<&dyn Index<TokenInfo<T>>> = vec![&self.owner];
         Box::new(v.into_iter())
     }
 }
 
-pub fn tokens<'a>() -> IndexedMap<'a, &'a str, PixelTokenInfo, TokenIndexes<'a>> {
+pub fn token_owner_idx<T>(d: &TokenInfo<T>, k: Vec<u8>) -> (Addr, Vec<u8>) {
+    (d.owner.clone(), k)
+}
+
+/// Tiles keyed by their big-endian position, so range scans follow the order of the wall.
+pub fn tokens<'a>() -> IndexedMap<'a, U16Key, PixelTokenInfo, TokenIndexes<'a, PixelExtension>> {
     let indexes = TokenIndexes {
-        owner: MultiIndex::new(
-            |d: &PixelTokenInfo, k: Vec<u8>| (d.owner.clone(), k),
-            "tokens",
-            "tokens__owner",
-        ),
+        owner: MultiIndex::new(token_owner_idx, "tiles", "tiles__owner"),
     };
-    IndexedMap::new("tokens", indexes)
+    IndexedMap::new("tiles", indexes)
+}
+
+/// Parses a cw721 token id into the position of the tile.
+pub fn parse_token_id(token_id: &str) -> StdResult<u16> {
+    token_id
+        .parse::<u16>()
+        .map_err(|_| StdError::generic_err(format!("Invalid token id: {}", token_id)))
+}
+
+/// Parses a primary key of `tokens()` back into the position of the tile.
+pub fn position_from_key(key: &[u8]) -> StdResult<u16> {
+    match key {
+        [high, low] => Ok(u16::from_be_bytes([*high, *low])),
+        _ => Err(StdError::generic_err("Invalid tile key")),
+    }
+}
+
+/// Number of previous versions kept for every tile.
+pub const MAX_HISTORY: usize = 10;
+
+/// A previous version of a tile's pixel data.
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct HistoryEntry {
+    pub extension: PixelExtension,
+    /// Block height at which this version was replaced.
+    pub height: u64,
+}
+
+/// Previous versions of every edited tile, oldest first and at most `MAX_HISTORY` of them.
+pub fn history<'a>() -> Map<'a, U16Key, Vec<HistoryEntry>> {
+    Map::new("history")
+}
+
+/// Block at which a tile's pixel data was last changed.
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct LastEdit {
+    pub height: u64,
+    pub time: Timestamp,
+}
+
+impl LastEdit {
+    /// Returns when `cooldown` after this edit ends. A cooldown reaching past the largest block
+    /// height or time never ends.
+    pub fn cooldown_end(&self, cooldown: &Duration) -> Expiration {
+        checked_end(self.height, self.time, cooldown).unwrap_or(Expiration::Never {})
+    }
+}
+
+/// Returns when `duration` after the given block height and time ends, or `None` if that lies
+/// past the largest block height or time.
+fn checked_end(height: u64, time: Timestamp, duration: &Duration) -> Option<Expiration> {
+    match *duration {
+        Duration::Height(blocks) => height.checked_add(blocks).map(Expiration::AtHeight),
+        Duration::Time(seconds) => seconds
+            .checked_mul(1_000_000_000)
+            .and_then(|nanos| time.nanos().checked_add(nanos))
+            .map(|nanos| Expiration::AtTime(Timestamp::from_nanos(nanos))),
+    }
+}
+
+impl From<&BlockInfo> for LastEdit {
+    fn from(block: &BlockInfo) -> Self {
+        LastEdit {
+            height: block.height,
+            time: block.time,
+        }
+    }
+}
+
+pub fn last_edits<'a>() -> Map<'a, U16Key, LastEdit> {
+    Map::new("last_edits")
+}
+
+/// Grants `editor` the right to change a tile's pixel data, without the right to transfer it.
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct EditorApproval {
+    pub editor: Addr,
+    /// Owner of the tile at the time of the grant. The approval lapses once the tile changes hands.
+    pub granter: Addr,
+    pub expires: Expiration,
+}
+
+impl EditorApproval {
+    pub fn is_valid(&self, owner: &Addr, block: &BlockInfo) -> bool {
+        self.granter == *owner && !self.expires.is_expired(block)
+    }
+}
+
+pub fn editors<'a>() -> Map<'a, (U16Key, &'a Addr), EditorApproval> {
+    Map::new("editors")
+}
+
+/// Sells reserved tiles one by one at a price falling linearly from `start_price` at `start`
+/// to `end_price` at `end`. Tiles left unsold at `end` can be minted as usual.
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct DutchAuction {
+    /// Tiles that have not been sold yet.
+    pub tiles: Vec<u16>,
+    pub start_price: Coin,
+    pub end_price: Coin,
+    pub start: Timestamp,
+    pub end: Timestamp,
+}
+
+impl DutchAuction {
+    pub fn is_active(&self, block: &BlockInfo) -> bool {
+        block.time >= self.start && block.time < self.end && !self.tiles.is_empty()
+    }
+
+    /// Returns the price of a tile at `time`.
+    pub fn price(&self, time: Timestamp) -> Coin {
+        let time = time.max(self.start).min(self.end);
+        let decay = (self.start_price.amount - self.end_price.amount).multiply_ratio(
+            time.nanos() - self.start.nanos(),
+            self.end.nanos() - self.start.nanos(),
+        );
+        Coin {
+            denom: self.start_price.denom.clone(),
+            amount: self.start_price.amount - decay,
+        }
+    }
+}
+
+pub fn dutch_auctions<'a>() -> Map<'a, U64Key, DutchAuction> {
+    Map::new("dutch_auctions")
+}
+
+/// Parses a primary key of `dutch_auctions()` back into the id of the auction.
+pub fn auction_id_from_key(key: &[u8]) -> StdResult<u64> {
+    let bytes: [u8; 8] = key
+        .try_into()
+        .map_err(|_| StdError::generic_err("Invalid auction key"))?;
+    Ok(u64::from_be_bytes(bytes))
+}
+
+pub fn dutch_auction_count<'a>() -> Item<'a, u64> {
+    Item::new("dutch_auction_count")
+}
+
+/// The auction each reserved tile is sold in.
+pub fn reserved_tiles<'a>() -> Map<'a, U16Key, u64> {
+    Map::new("reserved_tiles")
+}
+
+/// The highest bid of an English auction, held in escrow by the contract until it is outbid or
+/// the auction is settled.
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct Bid {
+    pub bidder: Addr,
+    pub amount: Coin,
+}
+
+/// Sells a reserved tile to the highest bidder once `expires` has passed.
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct EnglishAuction {
+    /// Lowest acceptable first bid. Its denom is the denom of all bids.
+    pub min_bid: Coin,
+    pub expires: Expiration,
+    pub highest_bid: Option<Bid>,
+}
+
+/// English auctions that have not been settled yet, keyed by the tile they sell.
+pub fn english_auctions<'a>() -> Map<'a, U16Key, EnglishAuction> {
+    Map::new("english_auctions")
+}
+
+/// A tile offered for sale by its owner. The tile stays with the seller until it is bought.
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct Listing {
+    pub seller: Addr,
+    pub price: Coin,
+}
+
+pub fn listings<'a>() -> Map<'a, U16Key, Listing> {
+    Map::new("listings")
+}
+
+/// An owner's offer to rent out the drawing rights of a tile for `duration`.
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct LeaseOffer {
+    pub lessor: Addr,
+    pub price: Coin,
+    pub duration: Duration,
+}
+
+impl LeaseOffer {
+    /// Returns when a lease accepted in `block` ends, or `None` if that lies past the largest
+    /// block height or time.
+    pub fn expiration(&self, block: &BlockInfo) -> Option<Expiration> {
+        checked_end(block.height, block.time, &self.duration)
+    }
+}
+
+pub fn lease_offers<'a>() -> Map<'a, U16Key, LeaseOffer> {
+    Map::new("lease_offers")
+}
+
+/// Gives `lessee` the exclusive right to change a tile's pixel data until `expires`. The lease
+/// outlives transfers of the tile.
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct Lease {
+    pub lessee: Addr,
+    pub expires: Expiration,
+}
+
+impl Lease {
+    pub fn is_active(&self, block: &BlockInfo) -> bool {
+        !self.expires.is_expired(block)
+    }
+}
+
+pub fn leases<'a>() -> Map<'a, U16Key, Lease> {
+    Map::new("leases")
 }
@@ -1,11 +1,35 @@
-use crate::contract::{execute, instantiate, query};
+// The original tests pass fees as `&[mint_fee.clone()]`. Older clippy releases do not know
+// this lint.
+#![allow(unknown_lints, clippy::cloned_ref_to_slice_refs)]
+
+use crate::contract::{execute, instantiate, migrate, query};
 use crate::error::ContractError;
-use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
-use crate::state::{config_read, Color, Config, PixelExtension, PixelTokenInfo};
+use crate::migrations::{legacy_tiles, legacy_tokens, LegacyPixelExtension};
+use crate::msg::{
+    AuctionInfo, AuctionsResponse, BalanceResponse, CanvasResponse, CheckRoyaltiesResponse,
+    ColorMapResponse, EditorsResponse, ExecuteMsg, InstantiateMsg, LeaseResponse, ListingInfo,
+    ListingsResponse, MigrateMsg, MintPriceResponse, MintTile, PixelDataChange,
+    PixelHistoryResponse, PixelInfosResponse, PixelUpdate, PriceZonesResponse, QueryMsg,
+    RoyaltiesInfoResponse, TileInfo,
+};
+use crate::pricing::PricingCurve;
+use crate::state::{
+    config_read, decode_color_map, encode_color_map, Bid, Color, ColorMap, Config, DutchAuction,
+    EditorApproval, EnglishAuction, HistoryEntry, Lease, Listing, PixelExtension, PixelMode,
+    PixelTokenInfo, PriceZone, Royalty, MAX_HISTORY,
+};
 use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
-use cosmwasm_std::{coin, coins, from_binary, Addr, Api, Deps, Response};
-use cw721::{OwnerOfResponse, TokensResponse};
+use cosmwasm_std::{
+    coin, coins, from_binary, from_slice, Addr, Api, BankMsg, Binary, CanonicalAddr, Coin, Decimal,
+    Deps, Env, Order, Response, Uint128,
+};
+use cosmwasm_storage::singleton;
+use cw0::Duration;
+use cw2::{get_contract_version, set_contract_version};
+use cw721::{Expiration, OwnerOfResponse, TokensResponse};
 use cw721_base::state::TokenInfo;
+use cw_storage_plus::U16Key;
+use serde::{Deserialize, Serialize};
 
 const TEST_CREATOR: &str = "creator";
 const TEST_MINT_FEE_AMOUNT: u128 = 2500000;
@@ -14,26 +38,52 @@ const TEST_USER2: &str = "user2";
 const TEST_TOKEN_ID1: u16 = 0;
 const TEST_TOKEN_ID2: u16 = 1;
 const TEST_URL: &str = "url";
-const TEST_COLORS: [[Color; 5]; 5] = [[Color {
+const TEST_COLOR: Color = Color {
     r: 2,
     g: 2,
     b: 2,
     a: 2,
-}; 5]; 5];
-const EMPTY_COLORS: [[Color; 5]; 5] = [[Color {
+};
+const EMPTY_COLOR: Color = Color {
     r: 0,
     g: 0,
     b: 0,
     a: 0,
-}; 5]; 5];
+};
+
+fn test_colors() -> ColorMap {
+    vec![vec![TEST_COLOR; 5]; 5]
+}
+
+fn empty_colors() -> ColorMap {
+    vec![vec![EMPTY_COLOR; 5]; 5]
+}
+
+/// Instantiate message for a wall charging `mint_fee`, with every other setting left at its
+/// default.
+fn instantiate_msg(mint_fee: Coin) -> InstantiateMsg {
+    InstantiateMsg {
+        mint_fee,
+        width: None,
+        height: None,
+        tile_size: None,
+        palette: None,
+        edit_cooldown: None,
+        edit_fee: None,
+        pricing: None,
+        platform_fee: None,
+        royalty: None,
+    }
+}
 
-fn get_token_info(owner: Addr, colors: [[Color; 5]; 5], url: String) -> TokenInfo<PixelExtension> {
+fn get_token_info(owner: Addr, colors: ColorMap, url: String) -> TokenInfo<PixelExtension> {
     TokenInfo::<PixelExtension> {
         owner,
         approvals: vec![],
         token_uri: None,
         extension: PixelExtension {
-            pixel_colors: colors,
+            pixels: encode_color_map(&colors),
+            mode: PixelMode::Rgba,
             url,
         },
     }
@@ -63,9 +113,7 @@ fn proper_initialization() {
     let mut deps = mock_dependencies(&[]);
 
     let mint_fee = coin(TEST_MINT_FEE_AMOUNT, "uusd");
-    let msg = InstantiateMsg {
-        mint_fee: mint_fee.clone(),
-    };
+    let msg = instantiate_msg(mint_fee.clone());
     let info = mock_info(TEST_CREATOR, &[]);
 
     // we can just call .unwrap() to assert this was a success
@@ -76,7 +124,17 @@ fn proper_initialization() {
     assert_eq!(
         Config {
             owner: deps.api.addr_canonicalize(TEST_CREATOR).unwrap(),
-            mint_fee
+            mint_fee,
+            width: 200,
+            height: 200,
+            tile_size: 5,
+            palette: None,
+            edit_cooldown: None,
+            edit_fee: None,
+            pricing: PricingCurve::Flat,
+            pending_owner: None,
+            platform_fee: Decimal::zero(),
+            royalty: None,
         },
         config
     );
@@ -87,9 +145,7 @@ fn can_mint_pixel() {
     let mut deps = mock_dependencies(&[]);
 
     let mint_fee = coin(TEST_MINT_FEE_AMOUNT, "uusd");
-    let msg = InstantiateMsg {
-        mint_fee: mint_fee.clone(),
-    };
+    let msg = instantiate_msg(mint_fee.clone());
     let info = mock_info(TEST_CREATOR, &[]);
 
     // we can just call .unwrap() to assert this was a success
@@ -100,6 +156,7 @@ fn can_mint_pixel() {
     let mint_msg = ExecuteMsg::Mint {
         token_id: TEST_TOKEN_ID1,
         color_map: None,
+        pixels: None,
         url: None,
     };
     // Mint first NFT.
@@ -112,13 +169,14 @@ fn can_mint_pixel() {
             .add_attribute("mint_fee", format!("{:?}", mint_fee))
             .add_attribute("token_id", TEST_TOKEN_ID1.to_string())
             .add_attribute("url", "")
-            .add_attribute("color_map", format!("{:?}", EMPTY_COLORS)),
+            .add_attribute("pixels", encode_color_map(&empty_colors()).to_base64()),
         res
     );
 
     let mint_msg = ExecuteMsg::Mint {
         token_id: TEST_TOKEN_ID2,
         color_map: None,
+        pixels: None,
         url: None,
     };
 
@@ -132,14 +190,14 @@ fn can_mint_pixel() {
             .add_attribute("mint_fee", format!("{:?}", mint_fee))
             .add_attribute("token_id", TEST_TOKEN_ID2.to_string())
             .add_attribute("url", "")
-            .add_attribute("color_map", format!("{:?}", EMPTY_COLORS)),
+            .add_attribute("pixels", encode_color_map(&empty_colors()).to_base64()),
         res
     );
 
     // Verify that first NFT was correctly created.
     let actual_token_info: PixelTokenInfo =
         pixel_info_query(deps.as_ref(), TEST_TOKEN_ID1.to_string());
-    let expected_token_info = get_token_info(user.sender.clone(), EMPTY_COLORS, "".to_string());
+    let expected_token_info = get_token_info(user.sender.clone(), empty_colors(), "".to_string());
     assert_eq!(expected_token_info, actual_token_info);
 
     // Check that we can get the owner of the first NFT.
@@ -193,9 +251,7 @@ fn can_not_mint_existing_pixel() {
     let mut deps = mock_dependencies(&[]);
 
     let mint_fee = coin(TEST_MINT_FEE_AMOUNT, "uusd");
-    let msg = InstantiateMsg {
-        mint_fee: mint_fee.clone(),
-    };
+    let msg = instantiate_msg(mint_fee.clone());
     let info = mock_info(TEST_CREATOR, &[]);
 
     // we can just call .unwrap() to assert this was a success
@@ -204,6 +260,7 @@ fn can_not_mint_existing_pixel() {
     let mint_msg = ExecuteMsg::Mint {
         token_id: TEST_TOKEN_ID1,
         color_map: None,
+        pixels: None,
         url: None,
     };
     let user = mock_info(TEST_USER, &[mint_fee]);
@@ -220,9 +277,7 @@ fn can_not_mint_out_of_range_pixel() {
     let mut deps = mock_dependencies(&[]);
 
     let mint_fee = coin(TEST_MINT_FEE_AMOUNT, "uusd");
-    let msg = InstantiateMsg {
-        mint_fee: mint_fee.clone(),
-    };
+    let msg = instantiate_msg(mint_fee.clone());
     let info = mock_info(TEST_CREATOR, &[]);
 
     // we can just call .unwrap() to assert this was a success
@@ -231,6 +286,7 @@ fn can_not_mint_out_of_range_pixel() {
     let mint_msg = ExecuteMsg::Mint {
         token_id: 40_000,
         color_map: None,
+        pixels: None,
         url: None,
     };
     let user = mock_info(TEST_USER, &[mint_fee]);
@@ -243,9 +299,7 @@ fn can_mint_pixel_with_url_and_colors() {
     let mut deps = mock_dependencies(&[]);
 
     let mint_fee = coin(TEST_MINT_FEE_AMOUNT, "uusd");
-    let msg = InstantiateMsg {
-        mint_fee: mint_fee.clone(),
-    };
+    let msg = instantiate_msg(mint_fee.clone());
     let info = mock_info(TEST_CREATOR, &[]);
 
     // we can just call .unwrap() to assert this was a success
@@ -255,7 +309,8 @@ fn can_mint_pixel_with_url_and_colors() {
 
     let mint_msg = ExecuteMsg::Mint {
         token_id: TEST_TOKEN_ID1,
-        color_map: Some(TEST_COLORS),
+        color_map: Some(test_colors()),
+        pixels: None,
         url: Some(TEST_URL.to_string()),
     };
 
@@ -268,14 +323,14 @@ fn can_mint_pixel_with_url_and_colors() {
             .add_attribute("mint_fee", format!("{:?}", mint_fee))
             .add_attribute("token_id", TEST_TOKEN_ID1.to_string())
             .add_attribute("url", TEST_URL)
-            .add_attribute("color_map", format!("{:?}", TEST_COLORS)),
+            .add_attribute("pixels", encode_color_map(&test_colors()).to_base64()),
         res
     );
 
     let actual_token_info: PixelTokenInfo =
         pixel_info_query(deps.as_ref(), TEST_TOKEN_ID1.to_string());
     let expected_token_info =
-        get_token_info(user.sender.clone(), TEST_COLORS, TEST_URL.to_string());
+        get_token_info(user.sender.clone(), test_colors(), TEST_URL.to_string());
     assert_eq!(expected_token_info, actual_token_info);
 }
 
@@ -284,9 +339,7 @@ fn can_change_url() {
     let mut deps = mock_dependencies(&[]);
 
     let mint_fee = coin(TEST_MINT_FEE_AMOUNT, "uusd");
-    let msg = InstantiateMsg {
-        mint_fee: mint_fee.clone(),
-    };
+    let msg = instantiate_msg(mint_fee.clone());
     let info = mock_info(TEST_CREATOR, &[]);
 
     // we can just call .unwrap() to assert this was a success
@@ -296,7 +349,8 @@ fn can_change_url() {
 
     let mint_msg = ExecuteMsg::Mint {
         token_id: TEST_TOKEN_ID1,
-        color_map: Some(TEST_COLORS),
+        color_map: Some(test_colors()),
+        pixels: None,
         url: None,
     };
 
@@ -309,18 +363,19 @@ fn can_change_url() {
             .add_attribute("mint_fee", format!("{:?}", mint_fee))
             .add_attribute("token_id", TEST_TOKEN_ID1.to_string())
             .add_attribute("url", "")
-            .add_attribute("color_map", format!("{:?}", TEST_COLORS)),
+            .add_attribute("pixels", encode_color_map(&test_colors()).to_base64()),
         res
     );
 
     let actual_token_info: PixelTokenInfo =
         pixel_info_query(deps.as_ref(), TEST_TOKEN_ID1.to_string());
-    let expected_token_info = get_token_info(user.sender.clone(), TEST_COLORS, "".to_string());
+    let expected_token_info = get_token_info(user.sender.clone(), test_colors(), "".to_string());
     assert_eq!(expected_token_info, actual_token_info);
 
     let change_url_msg = ExecuteMsg::ChangePixelData {
         token_id: TEST_TOKEN_ID1,
         color_map: None,
+        pixels: None,
         url: Some(TEST_URL.to_string()),
     };
 
@@ -330,15 +385,20 @@ fn can_change_url() {
         Response::new()
             .add_attribute("action", "change pixel data")
             .add_attribute("token_id", TEST_TOKEN_ID1.to_string())
-            .add_attribute("color_map", format!("{:?}", TEST_COLORS))
-            .add_attribute("url", TEST_URL.to_string()),
+            .add_attribute("pixels", encode_color_map(&test_colors()).to_base64())
+            .add_attribute("url", TEST_URL.to_string())
+            .add_attribute("refund", format!("{:?}", user.funds))
+            .add_message(BankMsg::Send {
+                to_address: TEST_USER.to_string(),
+                amount: user.funds.clone(),
+            }),
         res
     );
 
     let actual_token_info: PixelTokenInfo =
         pixel_info_query(deps.as_ref(), TEST_TOKEN_ID1.to_string());
     let expected_token_info =
-        get_token_info(user.sender.clone(), TEST_COLORS, TEST_URL.to_string());
+        get_token_info(user.sender.clone(), test_colors(), TEST_URL.to_string());
     assert_eq!(expected_token_info, actual_token_info);
 }
 
@@ -346,9 +406,7 @@ fn can_change_url() {
 fn cannot_change_url_unminted() {
     let mut deps = mock_dependencies(&[]);
     let mint_fee = coin(TEST_MINT_FEE_AMOUNT, "uusd");
-    let msg = InstantiateMsg {
-        mint_fee: mint_fee.clone(),
-    };
+    let msg = instantiate_msg(mint_fee.clone());
     let info = mock_info(TEST_CREATOR, &[]);
 
     // we can just call .unwrap() to assert this was a success
@@ -358,7 +416,8 @@ fn cannot_change_url_unminted() {
 
     let mint_msg = ExecuteMsg::Mint {
         token_id: TEST_TOKEN_ID1,
-        color_map: Some(TEST_COLORS),
+        color_map: Some(test_colors()),
+        pixels: None,
         url: None,
     };
 
@@ -367,6 +426,7 @@ fn cannot_change_url_unminted() {
     let change_url_msg = ExecuteMsg::ChangePixelData {
         token_id: TEST_TOKEN_ID2,
         color_map: None,
+        pixels: None,
         url: Some(TEST_URL.to_string()),
     };
 
@@ -380,9 +440,7 @@ fn cannot_change_url_not_owned() {
     let mut deps = mock_dependencies(&[]);
 
     let mint_fee = coin(TEST_MINT_FEE_AMOUNT, "uusd");
-    let msg = InstantiateMsg {
-        mint_fee: mint_fee.clone(),
-    };
+    let msg = instantiate_msg(mint_fee.clone());
     let info = mock_info(TEST_CREATOR, &[]);
 
     // we can just call .unwrap() to assert this was a success
@@ -393,7 +451,8 @@ fn cannot_change_url_not_owned() {
 
     let mint_msg = ExecuteMsg::Mint {
         token_id: TEST_TOKEN_ID1,
-        color_map: Some(TEST_COLORS),
+        color_map: Some(test_colors()),
+        pixels: None,
         url: None,
     };
 
@@ -402,6 +461,7 @@ fn cannot_change_url_not_owned() {
     let change_url_msg = ExecuteMsg::ChangePixelData {
         token_id: TEST_TOKEN_ID1,
         color_map: None,
+        pixels: None,
         url: Some(TEST_URL.to_string()),
     };
 
@@ -415,9 +475,7 @@ fn can_change_color() {
     let mut deps = mock_dependencies(&[]);
 
     let mint_fee = coin(TEST_MINT_FEE_AMOUNT, "uusd");
-    let msg = InstantiateMsg {
-        mint_fee: mint_fee.clone(),
-    };
+    let msg = instantiate_msg(mint_fee.clone());
     let info = mock_info(TEST_CREATOR, &[]);
 
     // we can just call .unwrap() to assert this was a success
@@ -428,6 +486,7 @@ fn can_change_color() {
     let mint_msg = ExecuteMsg::Mint {
         token_id: TEST_TOKEN_ID1,
         color_map: None,
+        pixels: None,
         url: Some(TEST_URL.to_string()),
     };
 
@@ -440,19 +499,20 @@ fn can_change_color() {
             .add_attribute("mint_fee", format!("{:?}", mint_fee))
             .add_attribute("token_id", TEST_TOKEN_ID1.to_string())
             .add_attribute("url", TEST_URL.to_string())
-            .add_attribute("color_map", format!("{:?}", EMPTY_COLORS)),
+            .add_attribute("pixels", encode_color_map(&empty_colors()).to_base64()),
         res
     );
 
     let actual_token_info: PixelTokenInfo =
         pixel_info_query(deps.as_ref(), TEST_TOKEN_ID1.to_string());
     let expected_token_info =
-        get_token_info(user.sender.clone(), EMPTY_COLORS, TEST_URL.to_string());
+        get_token_info(user.sender.clone(), empty_colors(), TEST_URL.to_string());
     assert_eq!(expected_token_info, actual_token_info);
 
     let change_color_msg = ExecuteMsg::ChangePixelData {
         token_id: TEST_TOKEN_ID1,
-        color_map: Some(TEST_COLORS),
+        color_map: Some(test_colors()),
+        pixels: None,
         url: None,
     };
 
@@ -462,15 +522,20 @@ fn can_change_color() {
         Response::new()
             .add_attribute("action", "change pixel data")
             .add_attribute("token_id", TEST_TOKEN_ID1.to_string())
-            .add_attribute("color_map", format!("{:?}", TEST_COLORS))
-            .add_attribute("url", TEST_URL.to_string()),
+            .add_attribute("pixels", encode_color_map(&test_colors()).to_base64())
+            .add_attribute("url", TEST_URL.to_string())
+            .add_attribute("refund", format!("{:?}", user.funds))
+            .add_message(BankMsg::Send {
+                to_address: TEST_USER.to_string(),
+                amount: user.funds.clone(),
+            }),
         res
     );
 
     let actual_token_info: PixelTokenInfo =
         pixel_info_query(deps.as_ref(), TEST_TOKEN_ID1.to_string());
     let expected_token_info =
-        get_token_info(user.sender.clone(), TEST_COLORS, TEST_URL.to_string());
+        get_token_info(user.sender.clone(), test_colors(), TEST_URL.to_string());
     assert_eq!(expected_token_info, actual_token_info);
 }
 
@@ -479,9 +544,7 @@ fn cannot_change_color_unminted() {
     let mut deps = mock_dependencies(&[]);
 
     let mint_fee = coin(TEST_MINT_FEE_AMOUNT, "uusd");
-    let msg = InstantiateMsg {
-        mint_fee: mint_fee.clone(),
-    };
+    let msg = instantiate_msg(mint_fee.clone());
     let info = mock_info(TEST_CREATOR, &[]);
 
     // we can just call .unwrap() to assert this was a success
@@ -492,6 +555,7 @@ fn cannot_change_color_unminted() {
     let mint_msg = ExecuteMsg::Mint {
         token_id: TEST_TOKEN_ID1,
         color_map: None,
+        pixels: None,
         url: Some(TEST_URL.to_string()),
     };
 
@@ -499,7 +563,8 @@ fn cannot_change_color_unminted() {
 
     let change_color_msg = ExecuteMsg::ChangePixelData {
         token_id: TEST_TOKEN_ID2,
-        color_map: Some(TEST_COLORS),
+        color_map: Some(test_colors()),
+        pixels: None,
         url: None,
     };
 
@@ -513,9 +578,7 @@ fn cannot_change_color_not_owned() {
     let mut deps = mock_dependencies(&[]);
 
     let mint_fee = coin(TEST_MINT_FEE_AMOUNT, "uusd");
-    let msg = InstantiateMsg {
-        mint_fee: mint_fee.clone(),
-    };
+    let msg = instantiate_msg(mint_fee.clone());
     let info = mock_info(TEST_CREATOR, &[]);
 
     // we can just call .unwrap() to assert this was a success
@@ -527,6 +590,7 @@ fn cannot_change_color_not_owned() {
     let mint_msg = ExecuteMsg::Mint {
         token_id: TEST_TOKEN_ID1,
         color_map: None,
+        pixels: None,
         url: Some(TEST_URL.to_string()),
     };
 
@@ -534,7 +598,8 @@ fn cannot_change_color_not_owned() {
 
     let change_color_msg = ExecuteMsg::ChangePixelData {
         token_id: TEST_TOKEN_ID1,
-        color_map: Some(TEST_COLORS),
+        color_map: Some(test_colors()),
+        pixels: None,
         url: None,
     };
 
@@ -548,9 +613,7 @@ fn can_not_mint_pixel_with_insufficient_funds() {
     let mut deps = mock_dependencies(&[]);
 
     let mint_fee = coin(TEST_MINT_FEE_AMOUNT, "uusd");
-    let msg = InstantiateMsg {
-        mint_fee: mint_fee.clone(),
-    };
+    let msg = instantiate_msg(mint_fee.clone());
     let info = mock_info(TEST_CREATOR, &[]);
 
     // we can just call .unwrap() to assert this was a success
@@ -559,6 +622,7 @@ fn can_not_mint_pixel_with_insufficient_funds() {
     let mint_msg = ExecuteMsg::Mint {
         token_id: TEST_TOKEN_ID1,
         color_map: None,
+        pixels: None,
         url: None,
     };
 
@@ -572,3 +636,3417 @@ fn can_not_mint_pixel_with_insufficient_funds() {
     let error = execute(deps.as_mut(), mock_env(), user.clone(), mint_msg.clone()).unwrap_err();
     assert_eq!(ContractError::InsufficientFunds {}, error);
 }
+
+#[test]
+fn can_withdraw_fees() {
+    let mut deps = mock_dependencies(&coins(TEST_MINT_FEE_AMOUNT * 2, "uusd"));
+
+    let mint_fee = coin(TEST_MINT_FEE_AMOUNT, "uusd");
+    let msg = instantiate_msg(mint_fee.clone());
+    let info = mock_info(TEST_CREATOR, &[]);
+
+    // we can just call .unwrap() to assert this was a success
+    let _res = instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+
+    let balance: BalanceResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Balance {}).unwrap()).unwrap();
+    assert_eq!(
+        BalanceResponse {
+            amount: coins(TEST_MINT_FEE_AMOUNT * 2, "uusd")
+        },
+        balance
+    );
+
+    let withdraw_msg = ExecuteMsg::WithdrawFees {
+        recipient: TEST_USER.to_string(),
+        amount: vec![mint_fee.clone()],
+    };
+    let res = execute(deps.as_mut(), mock_env(), info, withdraw_msg).unwrap();
+
+    assert_eq!(
+        Response::new()
+            .add_message(BankMsg::Send {
+                to_address: TEST_USER.to_string(),
+                amount: vec![mint_fee.clone()],
+            })
+            .add_attribute("action", "withdraw_fees")
+            .add_attribute("recipient", TEST_USER)
+            .add_attribute("amount", format!("{:?}", vec![mint_fee])),
+        res
+    );
+}
+
+#[test]
+fn cannot_withdraw_fees_not_owner() {
+    let mut deps = mock_dependencies(&coins(TEST_MINT_FEE_AMOUNT, "uusd"));
+
+    let mint_fee = coin(TEST_MINT_FEE_AMOUNT, "uusd");
+    let msg = instantiate_msg(mint_fee.clone());
+    let info = mock_info(TEST_CREATOR, &[]);
+
+    // we can just call .unwrap() to assert this was a success
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let withdraw_msg = ExecuteMsg::WithdrawFees {
+        recipient: TEST_USER.to_string(),
+        amount: vec![mint_fee],
+    };
+    let user = mock_info(TEST_USER, &[]);
+    let res = execute(deps.as_mut(), mock_env(), user, withdraw_msg);
+
+    assert_eq!(Err(ContractError::Unauthorized {}), res);
+}
+
+#[test]
+fn cannot_withdraw_more_than_balance() {
+    let mut deps = mock_dependencies(&coins(TEST_MINT_FEE_AMOUNT, "uusd"));
+
+    let mint_fee = coin(TEST_MINT_FEE_AMOUNT, "uusd");
+    let msg = instantiate_msg(mint_fee.clone());
+    let info = mock_info(TEST_CREATOR, &[]);
+
+    // we can just call .unwrap() to assert this was a success
+    let _res = instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+
+    let withdraw_msg = ExecuteMsg::WithdrawFees {
+        recipient: TEST_CREATOR.to_string(),
+        amount: coins(TEST_MINT_FEE_AMOUNT + 1, "uusd"),
+    };
+    let res = execute(deps.as_mut(), mock_env(), info, withdraw_msg);
+
+    assert_eq!(Err(ContractError::InsufficientFunds {}), res);
+}
+
+#[test]
+fn cannot_withdraw_escrowed_bids_by_repeating_denom() {
+    // 1000uusd of fees on top of an escrowed bid of 2000uusd.
+    let mut deps = mock_dependencies(&coins(3000, "uusd"));
+
+    let msg = instantiate_msg(coin(TEST_MINT_FEE_AMOUNT, "uusd"));
+    let creator = mock_info(TEST_CREATOR, &[]);
+    let _res = instantiate(deps.as_mut(), mock_env(), creator.clone(), msg).unwrap();
+
+    let auction_msg = ExecuteMsg::CreateEnglishAuction {
+        token_id: 7,
+        min_bid: coin(1000, "uusd"),
+        expires: Expiration::AtHeight(mock_env().block.height + 100),
+    };
+    let _res = execute(deps.as_mut(), mock_env(), creator.clone(), auction_msg).unwrap();
+    let _res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(TEST_USER, &coins(2000, "uusd")),
+        ExecuteMsg::PlaceBid { token_id: 7 },
+    )
+    .unwrap();
+
+    let withdraw_msg = ExecuteMsg::WithdrawFees {
+        recipient: TEST_CREATOR.to_string(),
+        amount: vec![coin(1000, "uusd"), coin(1000, "uusd")],
+    };
+    let res = execute(deps.as_mut(), mock_env(), creator.clone(), withdraw_msg);
+    assert_eq!(Err(ContractError::InsufficientFunds {}), res);
+
+    let withdraw_msg = ExecuteMsg::WithdrawFees {
+        recipient: TEST_CREATOR.to_string(),
+        amount: vec![],
+    };
+    let res = execute(deps.as_mut(), mock_env(), creator.clone(), withdraw_msg);
+    assert_eq!(Err(ContractError::InvalidWithdrawal {}), res);
+
+    let withdraw_msg = ExecuteMsg::WithdrawFees {
+        recipient: TEST_CREATOR.to_string(),
+        amount: vec![coin(1000, "uusd"), coin(0, "uluna")],
+    };
+    let res = execute(deps.as_mut(), mock_env(), creator.clone(), withdraw_msg);
+    assert_eq!(Err(ContractError::InvalidWithdrawal {}), res);
+
+    let withdraw_msg = ExecuteMsg::WithdrawFees {
+        recipient: TEST_CREATOR.to_string(),
+        amount: vec![coin(500, "uusd"), coin(500, "uusd")],
+    };
+    let res = execute(deps.as_mut(), mock_env(), creator, withdraw_msg).unwrap();
+    assert_eq!(
+        Response::new()
+            .add_message(BankMsg::Send {
+                to_address: TEST_CREATOR.to_string(),
+                amount: coins(1000, "uusd"),
+            })
+            .add_attribute("action", "withdraw_fees")
+            .add_attribute("recipient", TEST_CREATOR)
+            .add_attribute("amount", format!("{:?}", coins(1000, "uusd"))),
+        res
+    );
+}
+
+#[test]
+fn mint_refunds_overpayment_and_other_denoms() {
+    let mut deps = mock_dependencies(&[]);
+
+    let mint_fee = coin(TEST_MINT_FEE_AMOUNT, "uusd");
+    let msg = instantiate_msg(mint_fee.clone());
+    let info = mock_info(TEST_CREATOR, &[]);
+
+    // we can just call .unwrap() to assert this was a success
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let user = mock_info(
+        TEST_USER,
+        &[coin(TEST_MINT_FEE_AMOUNT + 100, "uusd"), coin(5, "uluna")],
+    );
+    let mint_msg = ExecuteMsg::Mint {
+        token_id: TEST_TOKEN_ID1,
+        color_map: None,
+        pixels: None,
+        url: None,
+    };
+    let res = execute(deps.as_mut(), mock_env(), user.clone(), mint_msg).unwrap();
+
+    let refund = vec![coin(100, "uusd"), coin(5, "uluna")];
+    assert_eq!(
+        Response::new()
+            .add_attribute("action", "mint_pixel")
+            .add_attribute("minter", user.sender.clone())
+            .add_attribute("mint_fee", format!("{:?}", mint_fee))
+            .add_attribute("token_id", TEST_TOKEN_ID1.to_string())
+            .add_attribute("url", "")
+            .add_attribute("pixels", encode_color_map(&empty_colors()).to_base64())
+            .add_attribute("refund", format!("{:?}", refund))
+            .add_message(BankMsg::Send {
+                to_address: user.sender.to_string(),
+                amount: refund,
+            }),
+        res
+    );
+}
+
+#[test]
+fn can_migrate_from_older_version() {
+    let mut deps = mock_dependencies(&[]);
+
+    let mint_fee = coin(TEST_MINT_FEE_AMOUNT, "uusd");
+    let msg = instantiate_msg(mint_fee.clone());
+    let info = mock_info(TEST_CREATOR, &[]);
+
+    // we can just call .unwrap() to assert this was a success
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    set_contract_version(deps.as_mut().storage, "crates.io:nft-pixel-wall", "0.0.1").unwrap();
+
+    let res = migrate(deps.as_mut(), mock_env(), MigrateMsg { limit: None }).unwrap();
+    assert_eq!(
+        Response::new()
+            .add_attribute("action", "migrate")
+            .add_attribute("from_version", "0.0.1")
+            .add_attribute("to_version", env!("CARGO_PKG_VERSION"))
+            .add_attribute("migrations", "0.2.0,0.3.0")
+            .add_attribute("complete", "true"),
+        res
+    );
+
+    let version = get_contract_version(deps.as_ref().storage).unwrap();
+    assert_eq!(env!("CARGO_PKG_VERSION"), version.version);
+
+    // Existing state survives the migration.
+    let config: Config = config_read(deps.as_ref().storage).load().unwrap();
+    assert_eq!(mint_fee, config.mint_fee);
+}
+
+#[test]
+fn cannot_migrate_other_contract() {
+    let mut deps = mock_dependencies(&[]);
+
+    set_contract_version(deps.as_mut().storage, "crates.io:cw721-base", "0.0.1").unwrap();
+
+    let res = migrate(deps.as_mut(), mock_env(), MigrateMsg { limit: None });
+    assert_eq!(
+        Err(ContractError::InvalidMigrationContract {
+            contract: "crates.io:cw721-base".to_string()
+        }),
+        res
+    );
+}
+
+#[test]
+fn cannot_migrate_to_older_version() {
+    let mut deps = mock_dependencies(&[]);
+
+    set_contract_version(deps.as_mut().storage, "crates.io:nft-pixel-wall", "999.0.0").unwrap();
+
+    let res = migrate(deps.as_mut(), mock_env(), MigrateMsg { limit: None });
+    assert_eq!(
+        Err(ContractError::CannotDowngrade {
+            from: "999.0.0".to_string(),
+            to: env!("CARGO_PKG_VERSION").to_string()
+        }),
+        res
+    );
+}
+
+/// Config as stored by the first release.
+#[derive(Serialize, Deserialize)]
+struct LegacyConfig {
+    owner: CanonicalAddr,
+    mint_fee: Coin,
+}
+
+#[test]
+fn can_migrate_state_of_first_release() {
+    let mut deps = mock_dependencies(&[]);
+
+    // State as written by 0.1.0: a config without any of the later settings and tiles keyed by
+    // their decimal token id, holding a color map.
+    let owner = deps.api.addr_canonicalize(TEST_CREATOR).unwrap();
+    let mint_fee = coin(TEST_MINT_FEE_AMOUNT, "uusd");
+    singleton(deps.as_mut().storage, b"config")
+        .save(&LegacyConfig {
+            owner: owner.clone(),
+            mint_fee: mint_fee.clone(),
+        })
+        .unwrap();
+    let token = TokenInfo {
+        owner: Addr::unchecked(TEST_USER),
+        approvals: vec![],
+        token_uri: None,
+        extension: LegacyPixelExtension {
+            pixel_colors: test_colors(),
+            url: TEST_URL.to_string(),
+        },
+    };
+    legacy_tokens()
+        .save(deps.as_mut().storage, "7", &token)
+        .unwrap();
+    set_contract_version(deps.as_mut().storage, "crates.io:nft-pixel-wall", "0.1.0").unwrap();
+
+    let res = migrate(deps.as_mut(), mock_env(), MigrateMsg { limit: None }).unwrap();
+    assert_eq!(
+        Response::new()
+            .add_attribute("action", "migrate")
+            .add_attribute("from_version", "0.1.0")
+            .add_attribute("to_version", env!("CARGO_PKG_VERSION"))
+            .add_attribute("migrations", "0.2.0,0.3.0")
+            .add_attribute("complete", "true"),
+        res
+    );
+
+    // Settings added since then take their defaults.
+    let config: Config = config_read(deps.as_ref().storage).load().unwrap();
+    assert_eq!(
+        Config {
+            owner,
+            mint_fee,
+            width: 200,
+            height: 200,
+            tile_size: 5,
+            palette: None,
+            edit_cooldown: None,
+            edit_fee: None,
+            pricing: PricingCurve::Flat,
+            pending_owner: None,
+            platform_fee: Decimal::zero(),
+            royalty: None,
+        },
+        config
+    );
+
+    assert_eq!(
+        get_token_info(
+            Addr::unchecked(TEST_USER),
+            test_colors(),
+            TEST_URL.to_string()
+        ),
+        pixel_info_query(deps.as_ref(), "7".to_string())
+    );
+}
+
+#[test]
+fn can_batch_mint_pixels() {
+    let mut deps = mock_dependencies(&[]);
+
+    let mint_fee = coin(TEST_MINT_FEE_AMOUNT, "uusd");
+    let msg = instantiate_msg(mint_fee.clone());
+    let info = mock_info(TEST_CREATOR, &[]);
+
+    // we can just call .unwrap() to assert this was a success
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let user = mock_info(TEST_USER, &coins(TEST_MINT_FEE_AMOUNT * 2, "uusd"));
+    let batch_msg = ExecuteMsg::BatchMint {
+        tiles: vec![
+            MintTile {
+                token_id: TEST_TOKEN_ID1,
+                color_map: None,
+                pixels: None,
+                url: None,
+            },
+            MintTile {
+                token_id: TEST_TOKEN_ID2,
+                color_map: Some(test_colors()),
+                pixels: None,
+                url: Some(TEST_URL.to_string()),
+            },
+        ],
+    };
+    let res = execute(deps.as_mut(), mock_env(), user.clone(), batch_msg).unwrap();
+
+    assert_eq!(
+        Response::new()
+            .add_attribute("action", "batch_mint_pixels")
+            .add_attribute("minter", user.sender.clone())
+            .add_attribute(
+                "mint_fee",
+                format!("{:?}", coin(TEST_MINT_FEE_AMOUNT * 2, "uusd"))
+            )
+            .add_attribute("token_ids", "0,1"),
+        res
+    );
+
+    let actual_token_info: PixelTokenInfo =
+        pixel_info_query(deps.as_ref(), TEST_TOKEN_ID1.to_string());
+    let expected_token_info = get_token_info(user.sender.clone(), empty_colors(), "".to_string());
+    assert_eq!(expected_token_info, actual_token_info);
+
+    let actual_token_info: PixelTokenInfo =
+        pixel_info_query(deps.as_ref(), TEST_TOKEN_ID2.to_string());
+    let expected_token_info =
+        get_token_info(user.sender.clone(), test_colors(), TEST_URL.to_string());
+    assert_eq!(expected_token_info, actual_token_info);
+}
+
+#[test]
+fn batch_mint_is_atomic() {
+    let mut deps = mock_dependencies(&[]);
+
+    let mint_fee = coin(TEST_MINT_FEE_AMOUNT, "uusd");
+    let msg = instantiate_msg(mint_fee.clone());
+    let info = mock_info(TEST_CREATOR, &[]);
+
+    // we can just call .unwrap() to assert this was a success
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let user = mock_info(TEST_USER, &coins(TEST_MINT_FEE_AMOUNT * 2, "uusd"));
+    let mint_msg = ExecuteMsg::Mint {
+        token_id: TEST_TOKEN_ID2,
+        color_map: None,
+        pixels: None,
+        url: None,
+    };
+    let _res = execute(deps.as_mut(), mock_env(), user.clone(), mint_msg).unwrap();
+
+    let tile = |token_id| MintTile {
+        token_id,
+        color_map: None,
+        pixels: None,
+        url: None,
+    };
+
+    // Already claimed tile fails the whole batch.
+    let batch_msg = ExecuteMsg::BatchMint {
+        tiles: vec![tile(TEST_TOKEN_ID1), tile(TEST_TOKEN_ID2)],
+    };
+    let error = execute(deps.as_mut(), mock_env(), user.clone(), batch_msg).unwrap_err();
+    assert_eq!(ContractError::Claimed {}, error);
+
+    // Duplicate tiles fail the whole batch.
+    let batch_msg = ExecuteMsg::BatchMint {
+        tiles: vec![tile(TEST_TOKEN_ID1), tile(TEST_TOKEN_ID1)],
+    };
+    let error = execute(deps.as_mut(), mock_env(), user.clone(), batch_msg).unwrap_err();
+    assert_eq!(ContractError::Claimed {}, error);
+
+    // Out of range tile fails the whole batch.
+    let batch_msg = ExecuteMsg::BatchMint {
+        tiles: vec![tile(TEST_TOKEN_ID1), tile(40_000)],
+    };
+    let error = execute(deps.as_mut(), mock_env(), user.clone(), batch_msg).unwrap_err();
+    assert_eq!(ContractError::InvalidTokenRange {}, error);
+
+    // Fee is charged per tile.
+    let batch_msg = ExecuteMsg::BatchMint {
+        tiles: vec![tile(TEST_TOKEN_ID1), tile(2), tile(3)],
+    };
+    let error = execute(deps.as_mut(), mock_env(), user.clone(), batch_msg).unwrap_err();
+    assert_eq!(ContractError::InsufficientFunds {}, error);
+
+    let batch_msg = ExecuteMsg::BatchMint { tiles: vec![] };
+    let error = execute(deps.as_mut(), mock_env(), user, batch_msg).unwrap_err();
+    assert_eq!(ContractError::NoTiles {}, error);
+
+    assert!(query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::PixelInfo {
+            token_id: TEST_TOKEN_ID1.to_string()
+        }
+    )
+    .is_err());
+}
+
+#[test]
+fn can_mint_region() {
+    let mut deps = mock_dependencies(&[]);
+
+    let mint_fee = coin(TEST_MINT_FEE_AMOUNT, "uusd");
+    let msg = instantiate_msg(mint_fee.clone());
+    let info = mock_info(TEST_CREATOR, &[]);
+
+    // we can just call .unwrap() to assert this was a success
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let user = mock_info(TEST_USER, &coins(TEST_MINT_FEE_AMOUNT * 4, "uusd"));
+    let region_msg = ExecuteMsg::MintRegion {
+        x: 199,
+        y: 0,
+        width: 1,
+        height: 4,
+        color_maps: Some(vec![
+            test_colors(),
+            empty_colors(),
+            test_colors(),
+            empty_colors(),
+        ]),
+    };
+    let res = execute(deps.as_mut(), mock_env(), user.clone(), region_msg).unwrap();
+
+    assert_eq!(
+        Response::new()
+            .add_attribute("action", "mint_region")
+            .add_attribute("minter", user.sender.clone())
+            .add_attribute(
+                "mint_fee",
+                format!("{:?}", coin(TEST_MINT_FEE_AMOUNT * 4, "uusd"))
+            )
+            .add_attribute("token_ids", "199,399,599,799"),
+        res
+    );
+
+    let actual_token_info: PixelTokenInfo = pixel_info_query(deps.as_ref(), "599".to_string());
+    let expected_token_info = get_token_info(user.sender.clone(), test_colors(), "".to_string());
+    assert_eq!(expected_token_info, actual_token_info);
+
+    // Regions must fit on the wall.
+    let region_msg = ExecuteMsg::MintRegion {
+        x: 199,
+        y: 0,
+        width: 2,
+        height: 1,
+        color_maps: None,
+    };
+    let error = execute(deps.as_mut(), mock_env(), user, region_msg).unwrap_err();
+    assert_eq!(ContractError::InvalidTokenRange {}, error);
+}
+
+#[test]
+fn cannot_mint_region_with_claimed_tiles() {
+    let mut deps = mock_dependencies(&[]);
+
+    let mint_fee = coin(TEST_MINT_FEE_AMOUNT, "uusd");
+    let msg = instantiate_msg(mint_fee.clone());
+    let info = mock_info(TEST_CREATOR, &[]);
+
+    // we can just call .unwrap() to assert this was a success
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let user = mock_info(TEST_USER, &coins(TEST_MINT_FEE_AMOUNT * 4, "uusd"));
+    for &token_id in &[TEST_TOKEN_ID2, 200] {
+        let mint_msg = ExecuteMsg::Mint {
+            token_id,
+            color_map: None,
+            pixels: None,
+            url: None,
+        };
+        let _res = execute(deps.as_mut(), mock_env(), user.clone(), mint_msg).unwrap();
+    }
+
+    let region_msg = ExecuteMsg::MintRegion {
+        x: 0,
+        y: 0,
+        width: 2,
+        height: 2,
+        color_maps: None,
+    };
+    let error = execute(deps.as_mut(), mock_env(), user.clone(), region_msg).unwrap_err();
+    assert_eq!(
+        ContractError::RegionClaimed {
+            token_ids: vec![TEST_TOKEN_ID2, 200]
+        },
+        error
+    );
+
+    let region_msg = ExecuteMsg::MintRegion {
+        x: 5,
+        y: 5,
+        width: 2,
+        height: 2,
+        color_maps: Some(vec![test_colors()]),
+    };
+    let error = execute(deps.as_mut(), mock_env(), user, region_msg).unwrap_err();
+    assert_eq!(ContractError::InvalidColorMaps {}, error);
+}
+
+#[test]
+fn can_batch_change_pixel_data() {
+    let mut deps = mock_dependencies(&[]);
+
+    let mint_fee = coin(TEST_MINT_FEE_AMOUNT, "uusd");
+    let msg = instantiate_msg(mint_fee.clone());
+    let info = mock_info(TEST_CREATOR, &[]);
+
+    // we can just call .unwrap() to assert this was a success
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let user = mock_info(TEST_USER, &coins(TEST_MINT_FEE_AMOUNT * 2, "uusd"));
+    let region_msg = ExecuteMsg::MintRegion {
+        x: 0,
+        y: 0,
+        width: 2,
+        height: 1,
+        color_maps: None,
+    };
+    let _res = execute(deps.as_mut(), mock_env(), user.clone(), region_msg).unwrap();
+
+    let batch_msg = ExecuteMsg::BatchChangePixelData {
+        changes: vec![
+            PixelDataChange {
+                token_id: TEST_TOKEN_ID2,
+                color_map: Some(test_colors()),
+                pixels: None,
+                url: None,
+            },
+            PixelDataChange {
+                token_id: TEST_TOKEN_ID1,
+                color_map: None,
+                pixels: None,
+                url: Some(TEST_URL.to_string()),
+            },
+            PixelDataChange {
+                token_id: TEST_TOKEN_ID2,
+                color_map: None,
+                pixels: None,
+                url: Some(TEST_URL.to_string()),
+            },
+        ],
+    };
+    let res = execute(deps.as_mut(), mock_env(), user.clone(), batch_msg).unwrap();
+
+    assert_eq!(
+        Response::new()
+            .add_attribute("action", "batch change pixel data")
+            .add_attribute("token_ids", "0,1")
+            .add_attribute("refund", format!("{:?}", user.funds))
+            .add_message(BankMsg::Send {
+                to_address: TEST_USER.to_string(),
+                amount: user.funds.clone(),
+            }),
+        res
+    );
+
+    let actual_token_info: PixelTokenInfo =
+        pixel_info_query(deps.as_ref(), TEST_TOKEN_ID1.to_string());
+    let expected_token_info =
+        get_token_info(user.sender.clone(), empty_colors(), TEST_URL.to_string());
+    assert_eq!(expected_token_info, actual_token_info);
+
+    let actual_token_info: PixelTokenInfo =
+        pixel_info_query(deps.as_ref(), TEST_TOKEN_ID2.to_string());
+    let expected_token_info =
+        get_token_info(user.sender.clone(), test_colors(), TEST_URL.to_string());
+    assert_eq!(expected_token_info, actual_token_info);
+}
+
+#[test]
+fn cannot_batch_change_pixel_data_not_owned() {
+    let mut deps = mock_dependencies(&[]);
+
+    let mint_fee = coin(TEST_MINT_FEE_AMOUNT, "uusd");
+    let msg = instantiate_msg(mint_fee.clone());
+    let info = mock_info(TEST_CREATOR, &[]);
+
+    // we can just call .unwrap() to assert this was a success
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let user = mock_info(TEST_USER, &coins(TEST_MINT_FEE_AMOUNT, "uusd"));
+    let user2 = mock_info(TEST_USER2, &coins(TEST_MINT_FEE_AMOUNT, "uusd"));
+    let mint_msg = ExecuteMsg::Mint {
+        token_id: TEST_TOKEN_ID1,
+        color_map: None,
+        pixels: None,
+        url: None,
+    };
+    let _res = execute(deps.as_mut(), mock_env(), user.clone(), mint_msg).unwrap();
+    let mint_msg = ExecuteMsg::Mint {
+        token_id: TEST_TOKEN_ID2,
+        color_map: None,
+        pixels: None,
+        url: None,
+    };
+    let _res = execute(deps.as_mut(), mock_env(), user2, mint_msg).unwrap();
+
+    let change = |token_id| PixelDataChange {
+        token_id,
+        color_map: Some(test_colors()),
+        pixels: None,
+        url: None,
+    };
+
+    let batch_msg = ExecuteMsg::BatchChangePixelData {
+        changes: vec![change(TEST_TOKEN_ID1), change(TEST_TOKEN_ID2)],
+    };
+    let res = execute(deps.as_mut(), mock_env(), user.clone(), batch_msg);
+    assert_eq!(Err(ContractError::Unauthorized {}), res);
+
+    let batch_msg = ExecuteMsg::BatchChangePixelData {
+        changes: vec![change(TEST_TOKEN_ID1), change(2)],
+    };
+    let res = execute(deps.as_mut(), mock_env(), user.clone(), batch_msg);
+    assert_eq!(Err(ContractError::DoesNotExist {}), res);
+
+    // Nothing was changed.
+    let actual_token_info: PixelTokenInfo =
+        pixel_info_query(deps.as_ref(), TEST_TOKEN_ID1.to_string());
+    let expected_token_info = get_token_info(user.sender, empty_colors(), "".to_string());
+    assert_eq!(expected_token_info, actual_token_info);
+}
+
+#[test]
+fn approved_editor_can_change_pixel_data() {
+    let mut deps = mock_dependencies(&[]);
+
+    let mint_fee = coin(TEST_MINT_FEE_AMOUNT, "uusd");
+    let msg = instantiate_msg(mint_fee.clone());
+    let info = mock_info(TEST_CREATOR, &[]);
+
+    // we can just call .unwrap() to assert this was a success
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let user = mock_info(TEST_USER, &coins(TEST_MINT_FEE_AMOUNT, "uusd"));
+    let user2 = mock_info(TEST_USER2, &[]);
+    let mint_msg = ExecuteMsg::Mint {
+        token_id: TEST_TOKEN_ID1,
+        color_map: None,
+        pixels: None,
+        url: None,
+    };
+    let _res = execute(deps.as_mut(), mock_env(), user.clone(), mint_msg).unwrap();
+
+    // Editors can only be approved by the owner.
+    let approve_msg = ExecuteMsg::ApproveEditor {
+        token_id: TEST_TOKEN_ID1,
+        editor: TEST_USER2.to_string(),
+        expires: None,
+    };
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        user2.clone(),
+        approve_msg.clone(),
+    );
+    assert_eq!(Err(ContractError::Unauthorized {}), res);
+
+    let _res = execute(deps.as_mut(), mock_env(), user.clone(), approve_msg).unwrap();
+
+    let editors: EditorsResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Editors {
+                token_id: TEST_TOKEN_ID1,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        EditorsResponse {
+            editors: vec![EditorApproval {
+                editor: user2.sender.clone(),
+                granter: user.sender.clone(),
+                expires: Expiration::Never {},
+            }]
+        },
+        editors
+    );
+
+    let change_color_msg = ExecuteMsg::ChangePixelData {
+        token_id: TEST_TOKEN_ID1,
+        color_map: Some(test_colors()),
+        pixels: None,
+        url: None,
+    };
+    let _res = execute(
+        deps.as_mut(),
+        mock_env(),
+        user2.clone(),
+        change_color_msg.clone(),
+    )
+    .unwrap();
+
+    let actual_token_info: PixelTokenInfo =
+        pixel_info_query(deps.as_ref(), TEST_TOKEN_ID1.to_string());
+    let expected_token_info = get_token_info(user.sender.clone(), test_colors(), "".to_string());
+    assert_eq!(expected_token_info, actual_token_info);
+
+    // Editors cannot transfer the tile.
+    let transfer_msg = ExecuteMsg::TransferNft {
+        recipient: TEST_USER2.to_string(),
+        token_id: TEST_TOKEN_ID1.to_string(),
+    };
+    let res = execute(deps.as_mut(), mock_env(), user2.clone(), transfer_msg);
+    assert_eq!(Err(ContractError::Unauthorized {}), res);
+
+    let revoke_msg = ExecuteMsg::RevokeEditor {
+        token_id: TEST_TOKEN_ID1,
+        editor: TEST_USER2.to_string(),
+    };
+    let _res = execute(deps.as_mut(), mock_env(), user, revoke_msg).unwrap();
+
+    let res = execute(deps.as_mut(), mock_env(), user2, change_color_msg);
+    assert_eq!(Err(ContractError::Unauthorized {}), res);
+}
+
+#[test]
+fn editor_approval_lapses_on_transfer_and_expiry() {
+    let mut deps = mock_dependencies(&[]);
+
+    let mint_fee = coin(TEST_MINT_FEE_AMOUNT, "uusd");
+    let msg = instantiate_msg(mint_fee.clone());
+    let info = mock_info(TEST_CREATOR, &[]);
+
+    // we can just call .unwrap() to assert this was a success
+    let _res = instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+
+    let user = mock_info(TEST_USER, &coins(TEST_MINT_FEE_AMOUNT * 2, "uusd"));
+    let user2 = mock_info(TEST_USER2, &[]);
+    let region_msg = ExecuteMsg::MintRegion {
+        x: 0,
+        y: 0,
+        width: 2,
+        height: 1,
+        color_maps: None,
+    };
+    let _res = execute(deps.as_mut(), mock_env(), user.clone(), region_msg).unwrap();
+
+    let env = mock_env();
+    let approve_msg = ExecuteMsg::ApproveEditor {
+        token_id: TEST_TOKEN_ID1,
+        editor: TEST_USER2.to_string(),
+        expires: Some(Expiration::AtHeight(env.block.height + 10)),
+    };
+    let _res = execute(deps.as_mut(), mock_env(), user.clone(), approve_msg).unwrap();
+    let approve_msg = ExecuteMsg::ApproveEditor {
+        token_id: TEST_TOKEN_ID2,
+        editor: TEST_USER2.to_string(),
+        expires: None,
+    };
+    let _res = execute(deps.as_mut(), mock_env(), user.clone(), approve_msg).unwrap();
+
+    let mut later = mock_env();
+    later.block.height += 10;
+    let change_color_msg = ExecuteMsg::ChangePixelData {
+        token_id: TEST_TOKEN_ID1,
+        color_map: Some(test_colors()),
+        pixels: None,
+        url: None,
+    };
+    let res = execute(deps.as_mut(), later, user2.clone(), change_color_msg);
+    assert_eq!(Err(ContractError::Unauthorized {}), res);
+
+    let transfer_msg = ExecuteMsg::TransferNft {
+        recipient: TEST_CREATOR.to_string(),
+        token_id: TEST_TOKEN_ID2.to_string(),
+    };
+    let _res = execute(deps.as_mut(), mock_env(), user, transfer_msg).unwrap();
+
+    let change_color_msg = ExecuteMsg::ChangePixelData {
+        token_id: TEST_TOKEN_ID2,
+        color_map: Some(test_colors()),
+        pixels: None,
+        url: None,
+    };
+    let res = execute(deps.as_mut(), mock_env(), user2, change_color_msg);
+    assert_eq!(Err(ContractError::Unauthorized {}), res);
+}
+
+#[test]
+fn can_query_canvas() {
+    let mut deps = mock_dependencies(&[]);
+
+    let mint_fee = coin(TEST_MINT_FEE_AMOUNT, "uusd");
+    let msg = instantiate_msg(mint_fee.clone());
+    let info = mock_info(TEST_CREATOR, &[]);
+
+    // we can just call .unwrap() to assert this was a success
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let user = mock_info(TEST_USER, &coins(TEST_MINT_FEE_AMOUNT, "uusd"));
+    let mint_msg = ExecuteMsg::Mint {
+        token_id: TEST_TOKEN_ID2,
+        color_map: Some(test_colors()),
+        pixels: None,
+        url: None,
+    };
+    let _res = execute(deps.as_mut(), mock_env(), user, mint_msg).unwrap();
+
+    let canvas: CanvasResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Canvas {
+                x: 0,
+                y: 0,
+                width: 2,
+                height: 1,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+
+    // Each row holds 5 transparent pixels of the unminted tile followed by 5 pixels of the
+    // minted one.
+    let row: Vec<u8> = [[0u8; 20], [2u8; 20]].concat();
+    assert_eq!(10, canvas.width);
+    assert_eq!(5, canvas.height);
+    assert_eq!(row.repeat(5), canvas.pixels.to_vec());
+
+    // Canvas must fit on the wall and stay within the tile limit.
+    let res = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::Canvas {
+            x: 199,
+            y: 0,
+            width: 2,
+            height: 1,
+        },
+    );
+    assert!(res.is_err());
+    let res = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::Canvas {
+            x: 0,
+            y: 0,
+            width: 200,
+            height: 200,
+        },
+    );
+    assert!(res.is_err());
+}
+
+#[test]
+fn can_query_canvas_at_end_of_largest_wall() {
+    let mut deps = mock_dependencies(&[]);
+
+    let msg = InstantiateMsg {
+        width: Some(256),
+        height: Some(256),
+        tile_size: Some(1),
+        ..instantiate_msg(coin(TEST_MINT_FEE_AMOUNT, "uusd"))
+    };
+    let _res = instantiate(deps.as_mut(), mock_env(), mock_info(TEST_CREATOR, &[]), msg).unwrap();
+
+    let user = mock_info(TEST_USER, &coins(TEST_MINT_FEE_AMOUNT, "uusd"));
+    let mint_msg = ExecuteMsg::Mint {
+        token_id: u16::MAX,
+        color_map: None,
+        pixels: Some(Binary(vec![1, 2, 3, 4])),
+        url: None,
+    };
+    let _res = execute(deps.as_mut(), mock_env(), user, mint_msg).unwrap();
+
+    let canvas: CanvasResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Canvas {
+                x: 254,
+                y: 255,
+                width: 2,
+                height: 1,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        CanvasResponse {
+            width: 2,
+            height: 1,
+            pixels: Binary(vec![0, 0, 0, 0, 1, 2, 3, 4]),
+        },
+        canvas
+    );
+}
+
+#[test]
+fn can_query_pixel_infos_in_position_order() {
+    let mut deps = mock_dependencies(&[]);
+
+    let mint_fee = coin(TEST_MINT_FEE_AMOUNT, "uusd");
+    let msg = instantiate_msg(mint_fee.clone());
+    let info = mock_info(TEST_CREATOR, &[]);
+
+    // we can just call .unwrap() to assert this was a success
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let user = mock_info(TEST_USER, &coins(TEST_MINT_FEE_AMOUNT, "uusd"));
+    let user2 = mock_info(TEST_USER2, &coins(TEST_MINT_FEE_AMOUNT, "uusd"));
+    for &(token_id, minter) in &[(10, &user), (9, &user2), (100, &user), (2, &user)] {
+        let mint_msg = ExecuteMsg::Mint {
+            token_id,
+            color_map: None,
+            pixels: None,
+            url: None,
+        };
+        let _res = execute(deps.as_mut(), mock_env(), minter.clone(), mint_msg).unwrap();
+    }
+
+    let token_ids = |msg: QueryMsg| -> Vec<u16> {
+        let res: PixelInfosResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        res.tiles.into_iter().map(|tile| tile.token_id).collect()
+    };
+
+    assert_eq!(
+        vec![2, 9, 10, 100],
+        token_ids(QueryMsg::PixelInfos {
+            start_after: None,
+            limit: None,
+        })
+    );
+    assert_eq!(
+        vec![10],
+        token_ids(QueryMsg::PixelInfos {
+            start_after: Some(9),
+            limit: Some(1),
+        })
+    );
+    assert_eq!(
+        vec![2, 10, 100],
+        token_ids(QueryMsg::PixelInfosByOwner {
+            owner: TEST_USER.to_string(),
+            start_after: None,
+            limit: None,
+        })
+    );
+    assert_eq!(
+        vec![100],
+        token_ids(QueryMsg::PixelInfosByOwner {
+            owner: TEST_USER.to_string(),
+            start_after: Some(10),
+            limit: None,
+        })
+    );
+
+    let res: PixelInfosResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::PixelInfosByOwner {
+                owner: TEST_USER2.to_string(),
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        PixelInfosResponse {
+            tiles: vec![TileInfo {
+                token_id: 9,
+                info: get_token_info(user2.sender, empty_colors(), "".to_string()),
+            }]
+        },
+        res
+    );
+}
+
+#[test]
+fn migration_rekeys_tokens_by_position() {
+    let mut deps = mock_dependencies(&[]);
+
+    let mint_fee = coin(TEST_MINT_FEE_AMOUNT, "uusd");
+    let msg = instantiate_msg(mint_fee.clone());
+    let info = mock_info(TEST_CREATOR, &[]);
+
+    // we can just call .unwrap() to assert this was a success
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    // Tiles as stored by 0.1.0, keyed by their decimal token id.
+    set_contract_version(deps.as_mut().storage, "crates.io:nft-pixel-wall", "0.1.0").unwrap();
+    let user = Addr::unchecked(TEST_USER);
+    for &token_id in &["10", "9", "100"] {
+        let token = TokenInfo {
+            owner: user.clone(),
+            approvals: vec![],
+            token_uri: None,
+            extension: LegacyPixelExtension {
+                pixel_colors: test_colors(),
+                url: token_id.to_string(),
+            },
+        };
+        legacy_tokens()
+            .save(deps.as_mut().storage, token_id, &token)
+            .unwrap();
+    }
+
+    // Large walls are migrated in batches, and the contract stays closed until it is done.
+    let res = migrate(deps.as_mut(), mock_env(), MigrateMsg { limit: Some(2) }).unwrap();
+    assert_eq!(
+        Response::new()
+            .add_attribute("action", "migrate")
+            .add_attribute("from_version", "0.1.0")
+            .add_attribute("to_version", "0.1.0")
+            .add_attribute("migrations", "")
+            .add_attribute("complete", "false"),
+        res
+    );
+    assert_eq!(
+        1,
+        legacy_tokens::<LegacyPixelExtension>()
+            .range(deps.as_ref().storage, None, None, Order::Ascending)
+            .count()
+    );
+    let mint_msg = ExecuteMsg::Mint {
+        token_id: TEST_TOKEN_ID1,
+        color_map: None,
+        pixels: None,
+        url: None,
+    };
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(TEST_USER, &coins(TEST_MINT_FEE_AMOUNT, "uusd")),
+        mint_msg,
+    );
+    assert_eq!(Err(ContractError::MigrationPending {}), res);
+
+    let res = migrate(deps.as_mut(), mock_env(), MigrateMsg { limit: None }).unwrap();
+    assert_eq!(
+        Response::new()
+            .add_attribute("action", "migrate")
+            .add_attribute("from_version", "0.1.0")
+            .add_attribute("to_version", env!("CARGO_PKG_VERSION"))
+            .add_attribute("migrations", "0.2.0,0.3.0")
+            .add_attribute("complete", "true"),
+        res
+    );
+
+    let tokens_response: TokensResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Tokens {
+                owner: TEST_USER.to_string(),
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        TokensResponse {
+            tokens: vec!["9".to_string(), "10".to_string(), "100".to_string()]
+        },
+        tokens_response
+    );
+
+    let actual_token_info: PixelTokenInfo = pixel_info_query(deps.as_ref(), "100".to_string());
+    let expected_token_info = get_token_info(user, test_colors(), "100".to_string());
+    assert_eq!(expected_token_info, actual_token_info);
+
+    // Nothing is left in the old storage.
+    let legacy = legacy_tokens::<LegacyPixelExtension>();
+    assert_eq!(
+        0,
+        legacy
+            .range(deps.as_ref().storage, None, None, Order::Ascending)
+            .count()
+    );
+    assert_eq!(
+        0,
+        legacy
+            .idx
+            .owner
+            .prefix(Addr::unchecked(TEST_USER))
+            .keys(deps.as_ref().storage, None, None, Order::Ascending)
+            .count()
+    );
+}
+
+#[test]
+fn can_transfer_and_approve_tiles() {
+    let mut deps = mock_dependencies(&[]);
+
+    let mint_fee = coin(TEST_MINT_FEE_AMOUNT, "uusd");
+    let msg = instantiate_msg(mint_fee.clone());
+    let info = mock_info(TEST_CREATOR, &[]);
+
+    // we can just call .unwrap() to assert this was a success
+    let _res = instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+
+    let user = mock_info(TEST_USER, &coins(TEST_MINT_FEE_AMOUNT, "uusd"));
+    let user2 = mock_info(TEST_USER2, &[]);
+    let mint_msg = ExecuteMsg::Mint {
+        token_id: TEST_TOKEN_ID1,
+        color_map: None,
+        pixels: None,
+        url: None,
+    };
+    let _res = execute(deps.as_mut(), mock_env(), user.clone(), mint_msg).unwrap();
+
+    let transfer_msg = ExecuteMsg::TransferNft {
+        recipient: TEST_USER2.to_string(),
+        token_id: TEST_TOKEN_ID1.to_string(),
+    };
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        user2.clone(),
+        transfer_msg.clone(),
+    );
+    assert_eq!(Err(ContractError::Unauthorized {}), res);
+
+    let approve_msg = ExecuteMsg::Approve {
+        spender: TEST_USER2.to_string(),
+        token_id: TEST_TOKEN_ID1.to_string(),
+        expires: None,
+    };
+    let _res = execute(deps.as_mut(), mock_env(), user.clone(), approve_msg).unwrap();
+
+    let owner_response: OwnerOfResponse = owner_of_query(deps.as_ref(), TEST_TOKEN_ID1.to_string());
+    assert_eq!(
+        OwnerOfResponse {
+            owner: TEST_USER.to_string(),
+            approvals: vec![cw721::Approval {
+                spender: TEST_USER2.to_string(),
+                expires: Expiration::Never {},
+            }]
+        },
+        owner_response
+    );
+
+    let res = execute(deps.as_mut(), mock_env(), user2.clone(), transfer_msg).unwrap();
+    assert_eq!(
+        Response::new()
+            .add_attribute("action", "transfer_nft")
+            .add_attribute("sender", TEST_USER2)
+            .add_attribute("recipient", TEST_USER2)
+            .add_attribute("token_id", TEST_TOKEN_ID1.to_string()),
+        res
+    );
+
+    // Approvals are cleared on transfer.
+    let owner_response: OwnerOfResponse = owner_of_query(deps.as_ref(), TEST_TOKEN_ID1.to_string());
+    assert_eq!(
+        OwnerOfResponse {
+            owner: TEST_USER2.to_string(),
+            approvals: vec![]
+        },
+        owner_response
+    );
+
+    let tokens_response: TokensResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::AllTokens {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        TokensResponse {
+            tokens: vec![TEST_TOKEN_ID1.to_string()]
+        },
+        tokens_response
+    );
+}
+
+#[test]
+fn can_instantiate_custom_wall() {
+    let mut deps = mock_dependencies(&[]);
+
+    let mint_fee = coin(TEST_MINT_FEE_AMOUNT, "uusd");
+    let msg = InstantiateMsg {
+        width: Some(4),
+        height: Some(3),
+        tile_size: Some(2),
+        ..instantiate_msg(mint_fee.clone())
+    };
+    let info = mock_info(TEST_CREATOR, &[]);
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let user = mock_info(TEST_USER, &coins(TEST_MINT_FEE_AMOUNT, "uusd"));
+    let small_colors = vec![vec![TEST_COLOR; 2]; 2];
+
+    // The last tile of the wall can be minted.
+    let mint_msg = ExecuteMsg::Mint {
+        token_id: 11,
+        color_map: Some(small_colors.clone()),
+        pixels: None,
+        url: None,
+    };
+    let _res = execute(deps.as_mut(), mock_env(), user.clone(), mint_msg).unwrap();
+    let actual_token_info: PixelTokenInfo = pixel_info_query(deps.as_ref(), "11".to_string());
+    assert_eq!(
+        get_token_info(user.sender.clone(), small_colors.clone(), "".to_string()),
+        actual_token_info
+    );
+
+    // Tiles default to an empty map of the configured size.
+    let mint_msg = ExecuteMsg::Mint {
+        token_id: 10,
+        color_map: None,
+        pixels: None,
+        url: None,
+    };
+    let _res = execute(deps.as_mut(), mock_env(), user.clone(), mint_msg).unwrap();
+    let actual_token_info: PixelTokenInfo = pixel_info_query(deps.as_ref(), "10".to_string());
+    assert_eq!(
+        get_token_info(
+            user.sender.clone(),
+            vec![vec![EMPTY_COLOR; 2]; 2],
+            "".to_string()
+        ),
+        actual_token_info
+    );
+
+    // Positions past the end of the wall are rejected.
+    let mint_msg = ExecuteMsg::Mint {
+        token_id: 12,
+        color_map: None,
+        pixels: None,
+        url: None,
+    };
+    let error = execute(deps.as_mut(), mock_env(), user.clone(), mint_msg).unwrap_err();
+    assert_eq!(ContractError::InvalidTokenRange {}, error);
+
+    // Color maps must match the configured tile size.
+    let mint_msg = ExecuteMsg::Mint {
+        token_id: 0,
+        color_map: Some(test_colors()),
+        pixels: None,
+        url: None,
+    };
+    let error = execute(deps.as_mut(), mock_env(), user.clone(), mint_msg).unwrap_err();
+    assert_eq!(ContractError::InvalidColorMapSize {}, error);
+
+    let change_msg = ExecuteMsg::ChangePixelData {
+        token_id: 11,
+        color_map: Some(vec![vec![TEST_COLOR; 2], vec![TEST_COLOR; 3]]),
+        pixels: None,
+        url: None,
+    };
+    let res = execute(deps.as_mut(), mock_env(), user.clone(), change_msg);
+    assert_eq!(Err(ContractError::InvalidColorMapSize {}), res);
+
+    // Regions are bounded by the wall's width.
+    let region_msg = ExecuteMsg::MintRegion {
+        x: 3,
+        y: 0,
+        width: 2,
+        height: 1,
+        color_maps: None,
+    };
+    let error = execute(deps.as_mut(), mock_env(), user, region_msg).unwrap_err();
+    assert_eq!(ContractError::InvalidTokenRange {}, error);
+}
+
+#[test]
+fn can_not_instantiate_invalid_wall() {
+    let mut deps = mock_dependencies(&[]);
+
+    let mint_fee = coin(TEST_MINT_FEE_AMOUNT, "uusd");
+    let info = mock_info(TEST_CREATOR, &[]);
+
+    let msg = InstantiateMsg {
+        width: Some(0),
+        ..instantiate_msg(mint_fee.clone())
+    };
+    let res = instantiate(deps.as_mut(), mock_env(), info.clone(), msg);
+    assert_eq!(Err(ContractError::InvalidDimensions {}), res);
+
+    let msg = InstantiateMsg {
+        width: Some(300),
+        height: Some(300),
+        ..instantiate_msg(mint_fee.clone())
+    };
+    let res = instantiate(deps.as_mut(), mock_env(), info.clone(), msg);
+    assert_eq!(Err(ContractError::InvalidDimensions {}), res);
+
+    let msg = InstantiateMsg {
+        tile_size: Some(0),
+        ..instantiate_msg(mint_fee)
+    };
+    let res = instantiate(deps.as_mut(), mock_env(), info, msg);
+    assert_eq!(Err(ContractError::InvalidDimensions {}), res);
+}
+
+#[test]
+fn can_mint_and_change_compact_pixels() {
+    let mut deps = mock_dependencies(&[]);
+
+    let mint_fee = coin(TEST_MINT_FEE_AMOUNT, "uusd");
+    let msg = instantiate_msg(mint_fee.clone());
+    let info = mock_info(TEST_CREATOR, &[]);
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let user = mock_info(TEST_USER, &coins(TEST_MINT_FEE_AMOUNT, "uusd"));
+    let pixels = Binary((0..100).collect());
+
+    let mint_msg = ExecuteMsg::Mint {
+        token_id: TEST_TOKEN_ID1,
+        color_map: None,
+        pixels: Some(pixels.clone()),
+        url: None,
+    };
+    let res = execute(deps.as_mut(), mock_env(), user.clone(), mint_msg).unwrap();
+    assert_eq!(
+        Response::new()
+            .add_attribute("action", "mint_pixel")
+            .add_attribute("minter", user.sender.clone())
+            .add_attribute("mint_fee", format!("{:?}", mint_fee))
+            .add_attribute("token_id", TEST_TOKEN_ID1.to_string())
+            .add_attribute("url", "")
+            .add_attribute("pixels", pixels.to_base64()),
+        res
+    );
+
+    let actual_token_info: PixelTokenInfo =
+        pixel_info_query(deps.as_ref(), TEST_TOKEN_ID1.to_string());
+    assert_eq!(pixels, actual_token_info.extension.pixels);
+    let color_map = decode_color_map(&actual_token_info.extension.pixels, 5);
+    assert_eq!(
+        Color {
+            r: 4,
+            g: 5,
+            b: 6,
+            a: 7
+        },
+        color_map[0][1]
+    );
+    assert_eq!(pixels, encode_color_map(&color_map));
+
+    // Color maps are stored in the same encoding.
+    let change_msg = ExecuteMsg::ChangePixelData {
+        token_id: TEST_TOKEN_ID1,
+        color_map: Some(test_colors()),
+        pixels: None,
+        url: None,
+    };
+    let _res = execute(deps.as_mut(), mock_env(), user.clone(), change_msg).unwrap();
+    let actual_token_info: PixelTokenInfo =
+        pixel_info_query(deps.as_ref(), TEST_TOKEN_ID1.to_string());
+    assert_eq!(Binary(vec![2; 100]), actual_token_info.extension.pixels);
+
+    let change_msg = ExecuteMsg::ChangePixelData {
+        token_id: TEST_TOKEN_ID1,
+        color_map: Some(test_colors()),
+        pixels: Some(pixels.clone()),
+        url: None,
+    };
+    let res = execute(deps.as_mut(), mock_env(), user.clone(), change_msg);
+    assert_eq!(Err(ContractError::ConflictingPixelData {}), res);
+
+    let change_msg = ExecuteMsg::ChangePixelData {
+        token_id: TEST_TOKEN_ID1,
+        color_map: None,
+        pixels: Some(Binary(vec![0; 99])),
+        url: None,
+    };
+    let res = execute(deps.as_mut(), mock_env(), user, change_msg);
+    assert_eq!(Err(ContractError::InvalidColorMapSize {}), res);
+}
+
+#[test]
+fn migration_encodes_pixels() {
+    let mut deps = mock_dependencies(&[]);
+
+    let mint_fee = coin(TEST_MINT_FEE_AMOUNT, "uusd");
+    let msg = instantiate_msg(mint_fee.clone());
+    let info = mock_info(TEST_CREATOR, &[]);
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    // Tiles as stored by 0.2.0, with a color map per tile.
+    set_contract_version(deps.as_mut().storage, "crates.io:nft-pixel-wall", "0.2.0").unwrap();
+    let user = Addr::unchecked(TEST_USER);
+    let token = TokenInfo {
+        owner: user.clone(),
+        approvals: vec![],
+        token_uri: None,
+        extension: LegacyPixelExtension {
+            pixel_colors: test_colors(),
+            url: TEST_URL.to_string(),
+        },
+    };
+    for &token_id in &[TEST_TOKEN_ID1, TEST_TOKEN_ID2, 7] {
+        legacy_tiles()
+            .save(deps.as_mut().storage, U16Key::new(token_id), &token)
+            .unwrap();
+    }
+
+    // Re-encoded tiles keep their key, so a batch continues after the previous one.
+    let res = migrate(deps.as_mut(), mock_env(), MigrateMsg { limit: Some(2) }).unwrap();
+    assert_eq!(
+        Response::new()
+            .add_attribute("action", "migrate")
+            .add_attribute("from_version", "0.2.0")
+            .add_attribute("to_version", "0.2.0")
+            .add_attribute("migrations", "")
+            .add_attribute("complete", "false"),
+        res
+    );
+
+    let res = migrate(deps.as_mut(), mock_env(), MigrateMsg { limit: Some(2) }).unwrap();
+    assert_eq!(
+        Response::new()
+            .add_attribute("action", "migrate")
+            .add_attribute("from_version", "0.2.0")
+            .add_attribute("to_version", env!("CARGO_PKG_VERSION"))
+            .add_attribute("migrations", "0.3.0")
+            .add_attribute("complete", "true"),
+        res
+    );
+
+    for &token_id in &[TEST_TOKEN_ID1, TEST_TOKEN_ID2, 7] {
+        let actual_token_info: PixelTokenInfo =
+            pixel_info_query(deps.as_ref(), token_id.to_string());
+        let expected_token_info = get_token_info(user.clone(), test_colors(), TEST_URL.to_string());
+        assert_eq!(expected_token_info, actual_token_info);
+    }
+
+    let tokens_response: TokensResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Tokens {
+                owner: TEST_USER.to_string(),
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        TokensResponse {
+            tokens: vec![
+                TEST_TOKEN_ID1.to_string(),
+                TEST_TOKEN_ID2.to_string(),
+                "7".to_string()
+            ]
+        },
+        tokens_response
+    );
+}
+
+#[test]
+fn can_paint_with_palette() {
+    let mut deps = mock_dependencies(&[]);
+
+    let red = Color {
+        r: 255,
+        g: 0,
+        b: 0,
+        a: 255,
+    };
+    let mint_fee = coin(TEST_MINT_FEE_AMOUNT, "uusd");
+    let msg = InstantiateMsg {
+        palette: Some(vec![TEST_COLOR, red]),
+        ..instantiate_msg(mint_fee.clone())
+    };
+    let info = mock_info(TEST_CREATOR, &[]);
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let user = mock_info(TEST_USER, &coins(2 * TEST_MINT_FEE_AMOUNT, "uusd"));
+
+    // Color maps are stored as palette indices.
+    let mint_msg = ExecuteMsg::Mint {
+        token_id: TEST_TOKEN_ID1,
+        color_map: Some(test_colors()),
+        pixels: None,
+        url: None,
+    };
+    let _res = execute(deps.as_mut(), mock_env(), user.clone(), mint_msg).unwrap();
+    let actual_token_info: PixelTokenInfo =
+        pixel_info_query(deps.as_ref(), TEST_TOKEN_ID1.to_string());
+    assert_eq!(
+        PixelExtension {
+            pixels: Binary(vec![0; 25]),
+            mode: PixelMode::Palette,
+            url: "".to_string(),
+        },
+        actual_token_info.extension
+    );
+
+    // So are indices given directly.
+    let mut indices = vec![0; 25];
+    indices[1] = 1;
+    let mint_msg = ExecuteMsg::Mint {
+        token_id: TEST_TOKEN_ID2,
+        color_map: None,
+        pixels: Some(Binary(indices)),
+        url: None,
+    };
+    let _res = execute(deps.as_mut(), mock_env(), user.clone(), mint_msg).unwrap();
+
+    let color_map_response: ColorMapResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::ColorMap {
+                token_id: TEST_TOKEN_ID2,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    let mut expected_colors = test_colors();
+    expected_colors[0][1] = red;
+    assert_eq!(
+        ColorMapResponse {
+            color_map: expected_colors
+        },
+        color_map_response
+    );
+
+    // Colors and indices outside the palette are rejected.
+    let change_msg = ExecuteMsg::ChangePixelData {
+        token_id: TEST_TOKEN_ID1,
+        color_map: Some(empty_colors()),
+        pixels: None,
+        url: None,
+    };
+    let res = execute(deps.as_mut(), mock_env(), user.clone(), change_msg);
+    assert_eq!(Err(ContractError::ColorNotInPalette {}), res);
+
+    let change_msg = ExecuteMsg::ChangePixelData {
+        token_id: TEST_TOKEN_ID1,
+        color_map: None,
+        pixels: Some(Binary(vec![2; 25])),
+        url: None,
+    };
+    let res = execute(deps.as_mut(), mock_env(), user.clone(), change_msg);
+    assert_eq!(Err(ContractError::ColorNotInPalette {}), res);
+
+    // RGBA bytes are rejected while the wall has a palette.
+    let change_msg = ExecuteMsg::ChangePixelData {
+        token_id: TEST_TOKEN_ID1,
+        color_map: None,
+        pixels: Some(Binary(vec![0; 100])),
+        url: None,
+    };
+    let res = execute(deps.as_mut(), mock_env(), user.clone(), change_msg);
+    assert_eq!(Err(ContractError::InvalidColorMapSize {}), res);
+
+    // Only the config owner can change the palette.
+    let blue = Color {
+        r: 0,
+        g: 0,
+        b: 255,
+        a: 255,
+    };
+    let palette_msg = ExecuteMsg::UpdatePalette {
+        palette: Some(vec![TEST_COLOR, red, blue]),
+    };
+    let res = execute(deps.as_mut(), mock_env(), user.clone(), palette_msg.clone());
+    assert_eq!(Err(ContractError::Unauthorized {}), res);
+
+    // Existing entries can be neither reordered, dropped nor removed, as tiles refer to them.
+    let creator = mock_info(TEST_CREATOR, &[]);
+    for palette in &[
+        Some(vec![red, TEST_COLOR]),
+        Some(vec![TEST_COLOR]),
+        Some(vec![]),
+        None,
+    ] {
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            creator.clone(),
+            ExecuteMsg::UpdatePalette {
+                palette: palette.clone(),
+            },
+        );
+        assert_eq!(Err(ContractError::InvalidPalette {}), res);
+    }
+
+    let res = execute(deps.as_mut(), mock_env(), creator, palette_msg).unwrap();
+    assert_eq!(
+        Response::new().add_attribute("action", "update_palette"),
+        res
+    );
+
+    // Palette tiles keep their colours, and appended colours can be painted.
+    let color_map_response: ColorMapResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::ColorMap {
+                token_id: TEST_TOKEN_ID1,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(test_colors(), color_map_response.color_map);
+
+    let change_msg = ExecuteMsg::ChangePixelData {
+        token_id: TEST_TOKEN_ID1,
+        color_map: None,
+        pixels: Some(Binary(vec![2; 25])),
+        url: None,
+    };
+    let _res = execute(deps.as_mut(), mock_env(), user, change_msg).unwrap();
+    let actual_token_info: PixelTokenInfo =
+        pixel_info_query(deps.as_ref(), TEST_TOKEN_ID1.to_string());
+    assert_eq!(
+        PixelExtension {
+            pixels: Binary(vec![2; 25]),
+            mode: PixelMode::Palette,
+            url: "".to_string(),
+        },
+        actual_token_info.extension
+    );
+}
+
+#[test]
+fn can_set_individual_pixels() {
+    let mut deps = mock_dependencies(&[]);
+
+    let mint_fee = coin(TEST_MINT_FEE_AMOUNT, "uusd");
+    let msg = instantiate_msg(mint_fee.clone());
+    let info = mock_info(TEST_CREATOR, &[]);
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let user = mock_info(TEST_USER, &coins(TEST_MINT_FEE_AMOUNT, "uusd"));
+    let mint_msg = ExecuteMsg::Mint {
+        token_id: TEST_TOKEN_ID1,
+        color_map: None,
+        pixels: None,
+        url: Some(TEST_URL.to_string()),
+    };
+    let _res = execute(deps.as_mut(), mock_env(), user.clone(), mint_msg).unwrap();
+
+    let set_msg = ExecuteMsg::SetPixels {
+        token_id: TEST_TOKEN_ID1,
+        pixels: vec![
+            PixelUpdate {
+                x: 1,
+                y: 0,
+                color: TEST_COLOR,
+            },
+            PixelUpdate {
+                x: 4,
+                y: 3,
+                color: TEST_COLOR,
+            },
+        ],
+    };
+    let res = execute(deps.as_mut(), mock_env(), user.clone(), set_msg).unwrap();
+    assert_eq!(
+        Response::new()
+            .add_attribute("action", "set pixels")
+            .add_attribute("token_id", TEST_TOKEN_ID1.to_string())
+            .add_attribute("pixels", "1,0;4,3")
+            .add_attribute("refund", format!("{:?}", user.funds))
+            .add_message(BankMsg::Send {
+                to_address: TEST_USER.to_string(),
+                amount: user.funds.clone(),
+            }),
+        res
+    );
+
+    let mut expected_colors = empty_colors();
+    expected_colors[0][1] = TEST_COLOR;
+    expected_colors[3][4] = TEST_COLOR;
+    let actual_token_info: PixelTokenInfo =
+        pixel_info_query(deps.as_ref(), TEST_TOKEN_ID1.to_string());
+    let expected_token_info =
+        get_token_info(user.sender.clone(), expected_colors, TEST_URL.to_string());
+    assert_eq!(expected_token_info, actual_token_info);
+
+    // Pixels must lie within the tile.
+    let set_msg = ExecuteMsg::SetPixels {
+        token_id: TEST_TOKEN_ID1,
+        pixels: vec![PixelUpdate {
+            x: 5,
+            y: 0,
+            color: TEST_COLOR,
+        }],
+    };
+    let res = execute(deps.as_mut(), mock_env(), user.clone(), set_msg);
+    assert_eq!(Err(ContractError::PixelOutOfRange { x: 5, y: 0 }), res);
+
+    let set_msg = ExecuteMsg::SetPixels {
+        token_id: TEST_TOKEN_ID1,
+        pixels: vec![],
+    };
+    let res = execute(deps.as_mut(), mock_env(), user, set_msg);
+    assert_eq!(Err(ContractError::NoPixels {}), res);
+
+    // Only those allowed to edit the tile can set its pixels.
+    let set_msg = ExecuteMsg::SetPixels {
+        token_id: TEST_TOKEN_ID1,
+        pixels: vec![PixelUpdate {
+            x: 0,
+            y: 0,
+            color: TEST_COLOR,
+        }],
+    };
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(TEST_USER2, &[]),
+        set_msg,
+    );
+    assert_eq!(Err(ContractError::Unauthorized {}), res);
+}
+
+#[test]
+fn can_undo_pixel_changes() {
+    let mut deps = mock_dependencies(&[]);
+
+    let mint_fee = coin(TEST_MINT_FEE_AMOUNT, "uusd");
+    let msg = instantiate_msg(mint_fee.clone());
+    let info = mock_info(TEST_CREATOR, &[]);
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let user = mock_info(TEST_USER, &coins(TEST_MINT_FEE_AMOUNT, "uusd"));
+    let mint_msg = ExecuteMsg::Mint {
+        token_id: TEST_TOKEN_ID1,
+        color_map: None,
+        pixels: None,
+        url: None,
+    };
+    let _res = execute(deps.as_mut(), mock_env(), user.clone(), mint_msg).unwrap();
+
+    let undo_msg = ExecuteMsg::UndoPixelChange {
+        token_id: TEST_TOKEN_ID1,
+    };
+    let res = execute(deps.as_mut(), mock_env(), user.clone(), undo_msg.clone());
+    assert_eq!(Err(ContractError::NoHistory {}), res);
+
+    let change_msg = ExecuteMsg::ChangePixelData {
+        token_id: TEST_TOKEN_ID1,
+        color_map: Some(test_colors()),
+        pixels: None,
+        url: Some(TEST_URL.to_string()),
+    };
+    let _res = execute(deps.as_mut(), mock_env(), user.clone(), change_msg).unwrap();
+
+    let set_msg = ExecuteMsg::SetPixels {
+        token_id: TEST_TOKEN_ID1,
+        pixels: vec![PixelUpdate {
+            x: 0,
+            y: 0,
+            color: EMPTY_COLOR,
+        }],
+    };
+    let _res = execute(deps.as_mut(), mock_env(), user.clone(), set_msg).unwrap();
+
+    let history_response: PixelHistoryResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::PixelHistory {
+                token_id: TEST_TOKEN_ID1,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    let height = mock_env().block.height;
+    assert_eq!(
+        PixelHistoryResponse {
+            entries: vec![
+                HistoryEntry {
+                    extension: get_token_info(
+                        user.sender.clone(),
+                        test_colors(),
+                        TEST_URL.to_string()
+                    )
+                    .extension,
+                    height,
+                },
+                HistoryEntry {
+                    extension: get_token_info(user.sender.clone(), empty_colors(), "".to_string())
+                        .extension,
+                    height,
+                },
+            ]
+        },
+        history_response
+    );
+
+    // Undo restores the most recent previous version first.
+    let res = execute(deps.as_mut(), mock_env(), user.clone(), undo_msg.clone()).unwrap();
+    assert_eq!(
+        Response::new()
+            .add_attribute("action", "undo pixel change")
+            .add_attribute("token_id", TEST_TOKEN_ID1.to_string())
+            .add_attribute("pixels", encode_color_map(&test_colors()).to_base64())
+            .add_attribute("url", TEST_URL)
+            .add_attribute("refund", format!("{:?}", user.funds))
+            .add_message(BankMsg::Send {
+                to_address: TEST_USER.to_string(),
+                amount: user.funds.clone(),
+            }),
+        res
+    );
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(TEST_USER2, &[]),
+        undo_msg.clone(),
+    );
+    assert_eq!(Err(ContractError::Unauthorized {}), res);
+
+    let _res = execute(deps.as_mut(), mock_env(), user.clone(), undo_msg.clone()).unwrap();
+    let actual_token_info: PixelTokenInfo =
+        pixel_info_query(deps.as_ref(), TEST_TOKEN_ID1.to_string());
+    let expected_token_info = get_token_info(user.sender.clone(), empty_colors(), "".to_string());
+    assert_eq!(expected_token_info, actual_token_info);
+
+    let res = execute(deps.as_mut(), mock_env(), user.clone(), undo_msg);
+    assert_eq!(Err(ContractError::NoHistory {}), res);
+
+    // Only the most recent versions are kept.
+    for _ in 0..MAX_HISTORY + 2 {
+        let set_msg = ExecuteMsg::SetPixels {
+            token_id: TEST_TOKEN_ID1,
+            pixels: vec![PixelUpdate {
+                x: 0,
+                y: 0,
+                color: TEST_COLOR,
+            }],
+        };
+        let _res = execute(deps.as_mut(), mock_env(), user.clone(), set_msg).unwrap();
+    }
+    let history_response: PixelHistoryResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::PixelHistory {
+                token_id: TEST_TOKEN_ID1,
+                limit: Some(100),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(MAX_HISTORY, history_response.entries.len());
+}
+
+#[test]
+fn can_not_edit_during_cooldown() {
+    let mut deps = mock_dependencies(&[]);
+
+    let mint_fee = coin(TEST_MINT_FEE_AMOUNT, "uusd");
+    let msg = InstantiateMsg {
+        edit_cooldown: Some(Duration::Height(10)),
+        ..instantiate_msg(mint_fee.clone())
+    };
+    let info = mock_info(TEST_CREATOR, &[]);
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let user = mock_info(TEST_USER, &coins(TEST_MINT_FEE_AMOUNT, "uusd"));
+    let mint_msg = ExecuteMsg::Mint {
+        token_id: TEST_TOKEN_ID1,
+        color_map: None,
+        pixels: None,
+        url: None,
+    };
+    let _res = execute(deps.as_mut(), mock_env(), user.clone(), mint_msg).unwrap();
+
+    // The first edit after minting is allowed.
+    let change_msg = ExecuteMsg::ChangePixelData {
+        token_id: TEST_TOKEN_ID1,
+        color_map: Some(test_colors()),
+        pixels: None,
+        url: None,
+    };
+    let _res = execute(deps.as_mut(), mock_env(), user.clone(), change_msg.clone()).unwrap();
+
+    let mut env = mock_env();
+    env.block.height += 9;
+    let until = Expiration::AtHeight(mock_env().block.height + 10);
+    let res = execute(deps.as_mut(), env.clone(), user.clone(), change_msg.clone());
+    assert_eq!(Err(ContractError::CooldownActive { until }), res);
+
+    let set_msg = ExecuteMsg::SetPixels {
+        token_id: TEST_TOKEN_ID1,
+        pixels: vec![PixelUpdate {
+            x: 0,
+            y: 0,
+            color: EMPTY_COLOR,
+        }],
+    };
+    let res = execute(deps.as_mut(), env.clone(), user.clone(), set_msg);
+    assert_eq!(Err(ContractError::CooldownActive { until }), res);
+
+    let undo_msg = ExecuteMsg::UndoPixelChange {
+        token_id: TEST_TOKEN_ID1,
+    };
+    let res = execute(deps.as_mut(), env.clone(), user.clone(), undo_msg);
+    assert_eq!(Err(ContractError::CooldownActive { until }), res);
+
+    env.block.height += 1;
+    let _res = execute(deps.as_mut(), env.clone(), user.clone(), change_msg).unwrap();
+
+    // The cooldown restarts with every edit.
+    let batch_msg = ExecuteMsg::BatchChangePixelData {
+        changes: vec![PixelDataChange {
+            token_id: TEST_TOKEN_ID1,
+            color_map: None,
+            pixels: None,
+            url: Some(TEST_URL.to_string()),
+        }],
+    };
+    let res = execute(deps.as_mut(), env.clone(), user.clone(), batch_msg.clone());
+    assert_eq!(
+        Err(ContractError::CooldownActive {
+            until: Expiration::AtHeight(env.block.height + 10)
+        }),
+        res
+    );
+
+    // A cooldown reaching past the largest timestamp never ends.
+    let update_msg = ExecuteMsg::UpdateConfig {
+        owner: None,
+        mint_fee: None,
+        edit_fee: None,
+        edit_cooldown: Some(Duration::Time(u64::MAX)),
+        pricing: None,
+        platform_fee: None,
+        royalty: None,
+    };
+    let _res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(TEST_CREATOR, &[]),
+        update_msg,
+    )
+    .unwrap();
+    let res = execute(deps.as_mut(), env, user, batch_msg);
+    assert_eq!(
+        Err(ContractError::CooldownActive {
+            until: Expiration::Never {}
+        }),
+        res
+    );
+}
+
+#[test]
+fn can_charge_edit_fee() {
+    let mut deps = mock_dependencies(&[]);
+
+    let mint_fee = coin(TEST_MINT_FEE_AMOUNT, "uusd");
+    let edit_fee = coin(1000, "uusd");
+    let msg = InstantiateMsg {
+        edit_fee: Some(edit_fee.clone()),
+        ..instantiate_msg(mint_fee.clone())
+    };
+    let info = mock_info(TEST_CREATOR, &[]);
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let user = mock_info(TEST_USER, &coins(2 * TEST_MINT_FEE_AMOUNT, "uusd"));
+    let batch_msg = ExecuteMsg::BatchMint {
+        tiles: vec![
+            MintTile {
+                token_id: TEST_TOKEN_ID1,
+                color_map: None,
+                pixels: None,
+                url: None,
+            },
+            MintTile {
+                token_id: TEST_TOKEN_ID2,
+                color_map: None,
+                pixels: None,
+                url: None,
+            },
+        ],
+    };
+    let _res = execute(deps.as_mut(), mock_env(), user.clone(), batch_msg).unwrap();
+
+    let change_msg = ExecuteMsg::ChangePixelData {
+        token_id: TEST_TOKEN_ID1,
+        color_map: None,
+        pixels: None,
+        url: Some(TEST_URL.to_string()),
+    };
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(TEST_USER, &[]),
+        change_msg.clone(),
+    );
+    assert_eq!(Err(ContractError::InsufficientFunds {}), res);
+
+    // Surplus funds are refunded.
+    let user = mock_info(TEST_USER, &coins(1500, "uusd"));
+    let res = execute(deps.as_mut(), mock_env(), user.clone(), change_msg.clone()).unwrap();
+    assert_eq!(
+        Response::new()
+            .add_attribute("action", "change pixel data")
+            .add_attribute("token_id", TEST_TOKEN_ID1.to_string())
+            .add_attribute("pixels", encode_color_map(&empty_colors()).to_base64())
+            .add_attribute("url", TEST_URL)
+            .add_attribute("edit_fee", format!("{:?}", edit_fee))
+            .add_attribute("refund", format!("{:?}", coins(500, "uusd")))
+            .add_message(BankMsg::Send {
+                to_address: TEST_USER.to_string(),
+                amount: coins(500, "uusd"),
+            }),
+        res
+    );
+
+    // Batches are charged per tile.
+    let batch_msg = ExecuteMsg::BatchChangePixelData {
+        changes: vec![
+            PixelDataChange {
+                token_id: TEST_TOKEN_ID1,
+                color_map: None,
+                pixels: None,
+                url: None,
+            },
+            PixelDataChange {
+                token_id: TEST_TOKEN_ID2,
+                color_map: None,
+                pixels: None,
+                url: None,
+            },
+        ],
+    };
+    let res = execute(deps.as_mut(), mock_env(), user.clone(), batch_msg.clone());
+    assert_eq!(Err(ContractError::InsufficientFunds {}), res);
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(TEST_USER, &coins(2000, "uusd")),
+        batch_msg,
+    )
+    .unwrap();
+    assert_eq!(
+        Response::new()
+            .add_attribute("action", "batch change pixel data")
+            .add_attribute(
+                "token_ids",
+                format!("{},{}", TEST_TOKEN_ID1, TEST_TOKEN_ID2)
+            )
+            .add_attribute("edit_fee", format!("{:?}", coin(2000, "uusd"))),
+        res
+    );
+
+    // Undoing a change is an edit as well.
+    let undo_msg = ExecuteMsg::UndoPixelChange {
+        token_id: TEST_TOKEN_ID1,
+    };
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(TEST_USER, &[]),
+        undo_msg.clone(),
+    );
+    assert_eq!(Err(ContractError::InsufficientFunds {}), res);
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(TEST_USER, &coins(1000, "uusd")),
+        undo_msg,
+    )
+    .unwrap();
+    assert_eq!(
+        Response::new()
+            .add_attribute("action", "undo pixel change")
+            .add_attribute("token_id", TEST_TOKEN_ID1.to_string())
+            .add_attribute("pixels", encode_color_map(&empty_colors()).to_base64())
+            .add_attribute("url", TEST_URL)
+            .add_attribute("edit_fee", format!("{:?}", edit_fee)),
+        res
+    );
+
+    // Only the config owner can change the fee, and a zero fee makes editing free.
+    let update_msg = ExecuteMsg::UpdateConfig {
+        owner: None,
+        mint_fee: None,
+        edit_fee: Some(coin(0, "uusd")),
+        edit_cooldown: None,
+        pricing: None,
+        platform_fee: None,
+        royalty: None,
+    };
+    let res = execute(deps.as_mut(), mock_env(), user, update_msg.clone());
+    assert_eq!(Err(ContractError::Unauthorized {}), res);
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(TEST_CREATOR, &[]),
+        update_msg,
+    )
+    .unwrap();
+    assert_eq!(
+        Response::new()
+            .add_attribute("action", "update_config")
+            .add_attribute("old_edit_fee", "1000uusd")
+            .add_attribute("new_edit_fee", "none"),
+        res
+    );
+    let config: Config = config_read(deps.as_ref().storage).load().unwrap();
+    assert_eq!(None, config.edit_fee);
+
+    let _res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(TEST_USER, &[]),
+        change_msg.clone(),
+    )
+    .unwrap();
+
+    // Funds sent to a free wall are returned.
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(TEST_USER, &coins(1000, "uusd")),
+        change_msg,
+    )
+    .unwrap();
+    assert_eq!(
+        Response::new()
+            .add_attribute("action", "change pixel data")
+            .add_attribute("token_id", TEST_TOKEN_ID1.to_string())
+            .add_attribute("pixels", encode_color_map(&empty_colors()).to_base64())
+            .add_attribute("url", TEST_URL)
+            .add_attribute("refund", format!("{:?}", coins(1000, "uusd")))
+            .add_message(BankMsg::Send {
+                to_address: TEST_USER.to_string(),
+                amount: coins(1000, "uusd"),
+            }),
+        res
+    );
+}
+
+#[test]
+fn can_update_config() {
+    let mut deps = mock_dependencies(&[]);
+
+    let mint_fee = coin(TEST_MINT_FEE_AMOUNT, "uusd");
+    let msg = instantiate_msg(mint_fee.clone());
+    let creator = mock_info(TEST_CREATOR, &[]);
+    let _res = instantiate(deps.as_mut(), mock_env(), creator.clone(), msg).unwrap();
+
+    let update_msg = ExecuteMsg::UpdateConfig {
+        owner: None,
+        mint_fee: Some(coin(100, "uluna")),
+        edit_fee: None,
+        edit_cooldown: Some(Duration::Time(60)),
+        pricing: None,
+        platform_fee: None,
+        royalty: None,
+    };
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(TEST_USER, &[]),
+        update_msg.clone(),
+    );
+    assert_eq!(Err(ContractError::Unauthorized {}), res);
+
+    // Changing the owner through the config is rejected instead of silently ignored.
+    let legacy_msg: ExecuteMsg = from_slice(br#"{"update_config":{"owner":"user2"}}"#).unwrap();
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(TEST_USER, &[]),
+        legacy_msg.clone(),
+    );
+    assert_eq!(Err(ContractError::Unauthorized {}), res);
+    let res = execute(deps.as_mut(), mock_env(), creator.clone(), legacy_msg);
+    assert_eq!(Err(ContractError::OwnerChangeRequiresProposal {}), res);
+
+    let res = execute(deps.as_mut(), mock_env(), creator.clone(), update_msg).unwrap();
+    assert_eq!(
+        Response::new()
+            .add_attribute("action", "update_config")
+            .add_attribute("old_mint_fee", "2500000uusd")
+            .add_attribute("new_mint_fee", "100uluna")
+            .add_attribute("old_edit_cooldown", "none")
+            .add_attribute("new_edit_cooldown", "time: 60"),
+        res
+    );
+
+    // Tiles are minted at the new fee.
+    let mint_msg = ExecuteMsg::Mint {
+        token_id: TEST_TOKEN_ID1,
+        color_map: None,
+        pixels: None,
+        url: None,
+    };
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(TEST_USER, &coins(TEST_MINT_FEE_AMOUNT, "uusd")),
+        mint_msg.clone(),
+    );
+    assert!(res.is_err());
+    let _res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(TEST_USER, &coins(100, "uluna")),
+        mint_msg,
+    )
+    .unwrap();
+
+    // Ownership only moves once the proposed owner accepts it.
+    let propose_msg = ExecuteMsg::ProposeOwner {
+        owner: TEST_USER.to_string(),
+    };
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(TEST_USER2, &[]),
+        propose_msg.clone(),
+    );
+    assert_eq!(Err(ContractError::Unauthorized {}), res);
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        creator.clone(),
+        propose_msg.clone(),
+    )
+    .unwrap();
+    assert_eq!(
+        Response::new()
+            .add_attribute("action", "propose_owner")
+            .add_attribute("pending_owner", TEST_USER),
+        res
+    );
+    let config: Config = config_read(deps.as_ref().storage).load().unwrap();
+    assert_eq!(
+        deps.api.addr_canonicalize(TEST_CREATOR).unwrap(),
+        config.owner
+    );
+
+    // The owner can withdraw the proposal before it is accepted.
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(TEST_USER, &[]),
+        ExecuteMsg::CancelOwnershipProposal {},
+    );
+    assert_eq!(Err(ContractError::Unauthorized {}), res);
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        creator.clone(),
+        ExecuteMsg::CancelOwnershipProposal {},
+    )
+    .unwrap();
+    assert_eq!(
+        Response::new().add_attribute("action", "cancel_ownership_proposal"),
+        res
+    );
+    let config: Config = config_read(deps.as_ref().storage).load().unwrap();
+    assert_eq!(None, config.pending_owner);
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(TEST_USER, &[]),
+        ExecuteMsg::AcceptOwnership {},
+    );
+    assert_eq!(Err(ContractError::Unauthorized {}), res);
+    let _res = execute(deps.as_mut(), mock_env(), creator.clone(), propose_msg).unwrap();
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(TEST_USER2, &[]),
+        ExecuteMsg::AcceptOwnership {},
+    );
+    assert_eq!(Err(ContractError::Unauthorized {}), res);
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(TEST_USER, &[]),
+        ExecuteMsg::AcceptOwnership {},
+    )
+    .unwrap();
+    assert_eq!(
+        Response::new()
+            .add_attribute("action", "accept_ownership")
+            .add_attribute("previous_owner", TEST_CREATOR)
+            .add_attribute("owner", TEST_USER),
+        res
+    );
+    let config: Config = config_read(deps.as_ref().storage).load().unwrap();
+    assert_eq!(deps.api.addr_canonicalize(TEST_USER).unwrap(), config.owner);
+    assert_eq!(None, config.pending_owner);
+
+    // The previous owner can no longer change the config.
+    let update_msg = ExecuteMsg::UpdateConfig {
+        owner: None,
+        mint_fee: Some(mint_fee),
+        edit_fee: None,
+        edit_cooldown: None,
+        pricing: None,
+        platform_fee: None,
+        royalty: None,
+    };
+    let res = execute(deps.as_mut(), mock_env(), creator, update_msg);
+    assert_eq!(Err(ContractError::Unauthorized {}), res);
+}
+
+fn mint_price_query(deps: Deps, token_id: u16) -> Coin {
+    let response: MintPriceResponse =
+        from_binary(&query(deps, mock_env(), QueryMsg::MintPrice { token_id }).unwrap()).unwrap();
+    response.price
+}
+
+#[test]
+fn can_price_mints_on_a_curve() {
+    let mut deps = mock_dependencies(&[]);
+
+    let msg = InstantiateMsg {
+        pricing: Some(PricingCurve::Linear {
+            increment: Uint128::new(100),
+        }),
+        ..instantiate_msg(coin(1000, "uusd"))
+    };
+    let creator = mock_info(TEST_CREATOR, &[]);
+    let _res = instantiate(deps.as_mut(), mock_env(), creator.clone(), msg).unwrap();
+
+    assert_eq!(coin(1000, "uusd"), mint_price_query(deps.as_ref(), 0));
+
+    let mint_msg = ExecuteMsg::Mint {
+        token_id: 0,
+        color_map: None,
+        pixels: None,
+        url: None,
+    };
+    let user = mock_info(TEST_USER, &coins(1000, "uusd"));
+    let _res = execute(deps.as_mut(), mock_env(), user, mint_msg).unwrap();
+    assert_eq!(coin(1100, "uusd"), mint_price_query(deps.as_ref(), 1));
+
+    // Every tile of a batch is priced by its place in the sequence of mints.
+    let region_msg = ExecuteMsg::MintRegion {
+        x: 1,
+        y: 0,
+        width: 2,
+        height: 1,
+        color_maps: None,
+    };
+    let user = mock_info(TEST_USER, &coins(2299, "uusd"));
+    let res = execute(deps.as_mut(), mock_env(), user, region_msg.clone());
+    assert_eq!(Err(ContractError::InsufficientFunds {}), res);
+    let user = mock_info(TEST_USER, &coins(2300, "uusd"));
+    let res = execute(deps.as_mut(), mock_env(), user.clone(), region_msg).unwrap();
+    assert_eq!(
+        Response::new()
+            .add_attribute("action", "mint_region")
+            .add_attribute("minter", user.sender)
+            .add_attribute("mint_fee", format!("{:?}", coin(2300, "uusd")))
+            .add_attribute("token_ids", "1,2"),
+        res
+    );
+
+    // Switch to a curve growing by 10% per tile.
+    let update_msg = ExecuteMsg::UpdateConfig {
+        owner: None,
+        mint_fee: None,
+        edit_fee: None,
+        edit_cooldown: None,
+        pricing: Some(PricingCurve::Exponential {
+            rate: Decimal::percent(10),
+        }),
+        platform_fee: None,
+        royalty: None,
+    };
+    let res = execute(deps.as_mut(), mock_env(), creator, update_msg).unwrap();
+    assert_eq!(
+        Response::new()
+            .add_attribute("action", "update_config")
+            .add_attribute("old_pricing", "linear: 100")
+            .add_attribute("new_pricing", "exponential: 0.1"),
+        res
+    );
+    assert_eq!(coin(1331, "uusd"), mint_price_query(deps.as_ref(), 3));
+
+    let res = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::MintPrice { token_id: 40000 },
+    );
+    assert!(res.is_err());
+}
+
+#[test]
+fn can_price_mints_by_location() {
+    let mut deps = mock_dependencies(&[]);
+
+    let msg = InstantiateMsg {
+        width: Some(10),
+        height: Some(10),
+        ..instantiate_msg(coin(1000, "uusd"))
+    };
+    let creator = mock_info(TEST_CREATOR, &[]);
+    let _res = instantiate(deps.as_mut(), mock_env(), creator.clone(), msg).unwrap();
+
+    // The center of the wall, with a premium spot in its middle.
+    let zones = vec![
+        PriceZone {
+            x: 4,
+            y: 4,
+            width: 2,
+            height: 2,
+            price: coin(9000, "uusd"),
+        },
+        PriceZone {
+            x: 3,
+            y: 3,
+            width: 4,
+            height: 4,
+            price: coin(3000, "uusd"),
+        },
+    ];
+    let zones_msg = ExecuteMsg::SetPriceZones {
+        zones: zones.clone(),
+    };
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(TEST_USER, &[]),
+        zones_msg.clone(),
+    );
+    assert_eq!(Err(ContractError::Unauthorized {}), res);
+
+    let res = execute(deps.as_mut(), mock_env(), creator.clone(), zones_msg).unwrap();
+    assert_eq!(
+        Response::new()
+            .add_attribute("action", "set_price_zones")
+            .add_attribute("zones", "2"),
+        res
+    );
+
+    let zones_response: PriceZonesResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::PriceZones {}).unwrap()).unwrap();
+    assert_eq!(PriceZonesResponse { zones }, zones_response);
+
+    assert_eq!(coin(1000, "uusd"), mint_price_query(deps.as_ref(), 0));
+    assert_eq!(coin(3000, "uusd"), mint_price_query(deps.as_ref(), 33));
+    assert_eq!(coin(9000, "uusd"), mint_price_query(deps.as_ref(), 44));
+    assert_eq!(coin(1000, "uusd"), mint_price_query(deps.as_ref(), 37));
+
+    // A region is charged the price of each of its tiles.
+    let region_msg = ExecuteMsg::MintRegion {
+        x: 2,
+        y: 3,
+        width: 3,
+        height: 2,
+        color_maps: None,
+    };
+    let user = mock_info(TEST_USER, &coins(2 * 1000 + 3 * 3000 + 9000 - 1, "uusd"));
+    let res = execute(deps.as_mut(), mock_env(), user, region_msg.clone());
+    assert_eq!(Err(ContractError::InsufficientFunds {}), res);
+    let user = mock_info(TEST_USER, &coins(2 * 1000 + 3 * 3000 + 9000, "uusd"));
+    let _res = execute(deps.as_mut(), mock_env(), user, region_msg).unwrap();
+
+    // Zones must lie on the wall and share the denom of the mint fee.
+    let zones_msg = ExecuteMsg::SetPriceZones {
+        zones: vec![PriceZone {
+            x: 8,
+            y: 0,
+            width: 3,
+            height: 1,
+            price: coin(3000, "uusd"),
+        }],
+    };
+    let res = execute(deps.as_mut(), mock_env(), creator.clone(), zones_msg);
+    assert_eq!(Err(ContractError::InvalidPriceZone {}), res);
+
+    let zones_msg = ExecuteMsg::SetPriceZones {
+        zones: vec![PriceZone {
+            x: 0,
+            y: 0,
+            width: 1,
+            height: 1,
+            price: coin(3000, "uluna"),
+        }],
+    };
+    let res = execute(deps.as_mut(), mock_env(), creator.clone(), zones_msg);
+    assert_eq!(Err(ContractError::InvalidPriceZone {}), res);
+
+    let update_msg = ExecuteMsg::UpdateConfig {
+        owner: None,
+        mint_fee: Some(coin(10, "uluna")),
+        edit_fee: None,
+        edit_cooldown: None,
+        pricing: None,
+        platform_fee: None,
+        royalty: None,
+    };
+    let res = execute(deps.as_mut(), mock_env(), creator, update_msg);
+    assert_eq!(Err(ContractError::InvalidPriceZone {}), res);
+}
+
+fn auctions_query(deps: Deps, env: Env) -> AuctionsResponse {
+    from_binary(
+        &query(
+            deps,
+            env,
+            QueryMsg::Auctions {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap()
+}
+
+#[test]
+fn can_sell_tiles_in_dutch_auction() {
+    let mut deps = mock_dependencies(&[]);
+
+    let mint_fee = coin(TEST_MINT_FEE_AMOUNT, "uusd");
+    let msg = instantiate_msg(mint_fee.clone());
+    let creator = mock_info(TEST_CREATOR, &[]);
+    let _res = instantiate(deps.as_mut(), mock_env(), creator.clone(), msg).unwrap();
+
+    let start = mock_env().block.time;
+    let auction_msg = ExecuteMsg::CreateAuction {
+        tiles: vec![5, 6],
+        start_price: coin(10000, "uusd"),
+        end_price: coin(2000, "uusd"),
+        start,
+        end: start.plus_seconds(1000),
+    };
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(TEST_USER, &[]),
+        auction_msg.clone(),
+    );
+    assert_eq!(Err(ContractError::Unauthorized {}), res);
+
+    let res = execute(deps.as_mut(), mock_env(), creator.clone(), auction_msg).unwrap();
+    assert_eq!(
+        Response::new()
+            .add_attribute("action", "create_auction")
+            .add_attribute("auction_id", "1")
+            .add_attribute("tiles", "[5, 6]"),
+        res
+    );
+
+    // Reserved tiles can not be minted or auctioned again.
+    let user = mock_info(TEST_USER, &coins(TEST_MINT_FEE_AMOUNT, "uusd"));
+    let mint_msg = ExecuteMsg::Mint {
+        token_id: 6,
+        color_map: None,
+        pixels: None,
+        url: None,
+    };
+    let res = execute(deps.as_mut(), mock_env(), user.clone(), mint_msg.clone());
+    assert_eq!(Err(ContractError::TileReserved {}), res);
+
+    let auction_msg = ExecuteMsg::CreateAuction {
+        tiles: vec![6, 7],
+        start_price: coin(10000, "uusd"),
+        end_price: coin(2000, "uusd"),
+        start,
+        end: start.plus_seconds(1000),
+    };
+    let res = execute(deps.as_mut(), mock_env(), creator, auction_msg);
+    assert_eq!(Err(ContractError::TileReserved {}), res);
+
+    // The price falls linearly.
+    let mut env = mock_env();
+    env.block.time = start.plus_seconds(500);
+    let auction = DutchAuction {
+        tiles: vec![5, 6],
+        start_price: coin(10000, "uusd"),
+        end_price: coin(2000, "uusd"),
+        start,
+        end: start.plus_seconds(1000),
+    };
+    assert_eq!(
+        AuctionsResponse {
+            auctions: vec![AuctionInfo {
+                id: 1,
+                auction: auction.clone(),
+                price: coin(6000, "uusd"),
+            }]
+        },
+        auctions_query(deps.as_ref(), env.clone())
+    );
+
+    let bid_msg = ExecuteMsg::BidAuction {
+        auction_id: 1,
+        token_id: 7,
+    };
+    let bidder = mock_info(TEST_USER2, &coins(7000, "uusd"));
+    let res = execute(deps.as_mut(), env.clone(), bidder.clone(), bid_msg);
+    assert_eq!(Err(ContractError::NotInAuction {}), res);
+
+    let bid_msg = ExecuteMsg::BidAuction {
+        auction_id: 1,
+        token_id: 5,
+    };
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(TEST_USER2, &coins(5999, "uusd")),
+        bid_msg.clone(),
+    );
+    assert_eq!(Err(ContractError::InsufficientFunds {}), res);
+
+    let res = execute(deps.as_mut(), env.clone(), bidder.clone(), bid_msg.clone()).unwrap();
+    assert_eq!(
+        Response::new()
+            .add_attribute("action", "bid_auction")
+            .add_attribute("auction_id", "1")
+            .add_attribute("minter", TEST_USER2)
+            .add_attribute("price", format!("{:?}", coin(6000, "uusd")))
+            .add_attribute("token_id", "5")
+            .add_attribute("refund", format!("{:?}", coins(1000, "uusd")))
+            .add_message(BankMsg::Send {
+                to_address: TEST_USER2.to_string(),
+                amount: coins(1000, "uusd"),
+            }),
+        res
+    );
+    assert_eq!(
+        TEST_USER2,
+        owner_of_query(deps.as_ref(), "5".to_string()).owner
+    );
+
+    let res = execute(deps.as_mut(), env.clone(), bidder, bid_msg);
+    assert_eq!(Err(ContractError::NotInAuction {}), res);
+
+    // Once the auction ends, unsold tiles are released.
+    env.block.time = start.plus_seconds(1000);
+    assert_eq!(
+        AuctionsResponse { auctions: vec![] },
+        auctions_query(deps.as_ref(), env.clone())
+    );
+    let bid_msg = ExecuteMsg::BidAuction {
+        auction_id: 1,
+        token_id: 6,
+    };
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(TEST_USER2, &coins(2000, "uusd")),
+        bid_msg,
+    );
+    assert_eq!(Err(ContractError::AuctionNotActive {}), res);
+
+    let _res = execute(deps.as_mut(), env, user, mint_msg).unwrap();
+}
+
+#[test]
+fn can_sell_tile_in_english_auction() {
+    // The contract holds the escrowed bid of 2000uusd once bidding is over.
+    let mut deps = mock_dependencies(&coins(2000, "uusd"));
+
+    let msg = instantiate_msg(coin(TEST_MINT_FEE_AMOUNT, "uusd"));
+    let creator = mock_info(TEST_CREATOR, &[]);
+    let _res = instantiate(deps.as_mut(), mock_env(), creator.clone(), msg).unwrap();
+
+    let expires = Expiration::AtHeight(mock_env().block.height + 100);
+    let auction_msg = ExecuteMsg::CreateEnglishAuction {
+        token_id: 7,
+        min_bid: coin(1000, "uusd"),
+        expires,
+    };
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(TEST_USER, &[]),
+        auction_msg.clone(),
+    );
+    assert_eq!(Err(ContractError::Unauthorized {}), res);
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        creator.clone(),
+        ExecuteMsg::CreateEnglishAuction {
+            token_id: 7,
+            min_bid: coin(0, "uusd"),
+            expires,
+        },
+    );
+    assert_eq!(Err(ContractError::InvalidAuction {}), res);
+
+    let res = execute(deps.as_mut(), mock_env(), creator.clone(), auction_msg).unwrap();
+    assert_eq!(
+        Response::new()
+            .add_attribute("action", "create_english_auction")
+            .add_attribute("token_id", "7")
+            .add_attribute("min_bid", format!("{:?}", coin(1000, "uusd")))
+            .add_attribute("expires", expires.to_string()),
+        res
+    );
+
+    let mint_msg = ExecuteMsg::Mint {
+        token_id: 7,
+        color_map: None,
+        pixels: None,
+        url: None,
+    };
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(TEST_USER, &coins(TEST_MINT_FEE_AMOUNT, "uusd")),
+        mint_msg,
+    );
+    assert_eq!(Err(ContractError::TileReserved {}), res);
+
+    let bid_msg = ExecuteMsg::PlaceBid { token_id: 7 };
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(TEST_USER, &coins(500, "uusd")),
+        bid_msg.clone(),
+    );
+    assert_eq!(
+        Err(ContractError::BidTooLow {
+            min_bid: coin(1000, "uusd")
+        }),
+        res
+    );
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(TEST_USER, &coins(1000, "uusd")),
+        bid_msg.clone(),
+    )
+    .unwrap();
+    assert_eq!(
+        Response::new()
+            .add_attribute("action", "place_bid")
+            .add_attribute("token_id", "7")
+            .add_attribute("bidder", TEST_USER)
+            .add_attribute("amount", format!("{:?}", coin(1000, "uusd"))),
+        res
+    );
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(TEST_USER2, &coins(1000, "uusd")),
+        bid_msg.clone(),
+    );
+    assert_eq!(
+        Err(ContractError::BidTooLow {
+            min_bid: coin(1001, "uusd")
+        }),
+        res
+    );
+
+    // Outbidding refunds the previous bid, and coins of other denoms are returned.
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(TEST_USER2, &[coin(2000, "uusd"), coin(5, "uluna")]),
+        bid_msg.clone(),
+    )
+    .unwrap();
+    assert_eq!(
+        Response::new()
+            .add_attribute("action", "place_bid")
+            .add_attribute("token_id", "7")
+            .add_attribute("bidder", TEST_USER2)
+            .add_attribute("amount", format!("{:?}", coin(2000, "uusd")))
+            .add_attribute("outbid", TEST_USER)
+            .add_message(BankMsg::Send {
+                to_address: TEST_USER.to_string(),
+                amount: coins(1000, "uusd"),
+            })
+            .add_attribute("refund", format!("{:?}", coins(5, "uluna")))
+            .add_message(BankMsg::Send {
+                to_address: TEST_USER2.to_string(),
+                amount: coins(5, "uluna"),
+            }),
+        res
+    );
+
+    let auction: EnglishAuction = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::EnglishAuction { token_id: 7 },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        EnglishAuction {
+            min_bid: coin(1000, "uusd"),
+            expires,
+            highest_bid: Some(Bid {
+                bidder: Addr::unchecked(TEST_USER2),
+                amount: coin(2000, "uusd"),
+            }),
+        },
+        auction
+    );
+
+    // Escrowed bids can not be withdrawn.
+    let withdraw_msg = ExecuteMsg::WithdrawFees {
+        recipient: TEST_CREATOR.to_string(),
+        amount: coins(1, "uusd"),
+    };
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        creator.clone(),
+        withdraw_msg.clone(),
+    );
+    assert_eq!(Err(ContractError::InsufficientFunds {}), res);
+
+    let settle_msg = ExecuteMsg::SettleAuction { token_id: 7 };
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(TEST_USER, &[]),
+        settle_msg.clone(),
+    );
+    assert_eq!(Err(ContractError::AuctionNotEnded {}), res);
+
+    let mut env = mock_env();
+    env.block.height += 100;
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(TEST_USER, &coins(3000, "uusd")),
+        bid_msg,
+    );
+    assert_eq!(Err(ContractError::AuctionNotActive {}), res);
+
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(TEST_USER, &[]),
+        settle_msg.clone(),
+    )
+    .unwrap();
+    assert_eq!(
+        Response::new()
+            .add_attribute("action", "settle_auction")
+            .add_attribute("token_id", "7")
+            .add_attribute("minter", TEST_USER2)
+            .add_attribute("price", format!("{:?}", coin(2000, "uusd"))),
+        res
+    );
+    assert_eq!(
+        TEST_USER2,
+        owner_of_query(deps.as_ref(), "7".to_string()).owner
+    );
+
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(TEST_USER, &[]),
+        settle_msg,
+    );
+    assert_eq!(Err(ContractError::AuctionNotActive {}), res);
+
+    // The winning bid is now a withdrawable fee.
+    let _res = execute(deps.as_mut(), env, creator, withdraw_msg).unwrap();
+}
+
+#[test]
+fn can_trade_tiles_on_marketplace() {
+    let mut deps = mock_dependencies(&[]);
+
+    let msg = InstantiateMsg {
+        platform_fee: Some(Decimal::percent(5)),
+        ..instantiate_msg(coin(TEST_MINT_FEE_AMOUNT, "uusd"))
+    };
+    let _res = instantiate(deps.as_mut(), mock_env(), mock_info(TEST_CREATOR, &[]), msg).unwrap();
+
+    let seller = mock_info(TEST_USER, &[]);
+    let mint_msg = ExecuteMsg::Mint {
+        token_id: 3,
+        color_map: None,
+        pixels: None,
+        url: None,
+    };
+    let _res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(TEST_USER, &coins(TEST_MINT_FEE_AMOUNT, "uusd")),
+        mint_msg,
+    )
+    .unwrap();
+
+    let list_msg = ExecuteMsg::ListTile {
+        token_id: 3,
+        price: coin(1000, "uusd"),
+    };
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(TEST_USER2, &[]),
+        list_msg.clone(),
+    );
+    assert_eq!(Err(ContractError::Unauthorized {}), res);
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        seller.clone(),
+        ExecuteMsg::ListTile {
+            token_id: 4,
+            price: coin(1000, "uusd"),
+        },
+    );
+    assert_eq!(Err(ContractError::DoesNotExist {}), res);
+
+    let res = execute(deps.as_mut(), mock_env(), seller.clone(), list_msg.clone()).unwrap();
+    assert_eq!(
+        Response::new()
+            .add_attribute("action", "list_tile")
+            .add_attribute("token_id", "3")
+            .add_attribute("seller", TEST_USER)
+            .add_attribute("price", format!("{:?}", coin(1000, "uusd"))),
+        res
+    );
+
+    let listings: ListingsResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Listings {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        ListingsResponse {
+            listings: vec![ListingInfo {
+                token_id: 3,
+                listing: Listing {
+                    seller: Addr::unchecked(TEST_USER),
+                    price: coin(1000, "uusd"),
+                },
+            }]
+        },
+        listings
+    );
+
+    let buy_msg = ExecuteMsg::BuyTile { token_id: 3 };
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(TEST_USER2, &coins(900, "uusd")),
+        buy_msg.clone(),
+    );
+    assert_eq!(Err(ContractError::InsufficientFunds {}), res);
+
+    // The seller receives the price minus the 5% platform fee.
+    let buyer = mock_info(TEST_USER2, &coins(1100, "uusd"));
+    let res = execute(deps.as_mut(), mock_env(), buyer.clone(), buy_msg.clone()).unwrap();
+    assert_eq!(
+        Response::new()
+            .add_attribute("action", "buy_tile")
+            .add_attribute("token_id", "3")
+            .add_attribute("seller", TEST_USER)
+            .add_attribute("buyer", TEST_USER2)
+            .add_attribute("price", format!("{:?}", coin(1000, "uusd")))
+            .add_attribute("platform_fee", "50")
+            .add_message(BankMsg::Send {
+                to_address: TEST_USER.to_string(),
+                amount: coins(950, "uusd"),
+            })
+            .add_attribute("refund", format!("{:?}", coins(100, "uusd")))
+            .add_message(BankMsg::Send {
+                to_address: TEST_USER2.to_string(),
+                amount: coins(100, "uusd"),
+            }),
+        res
+    );
+    assert_eq!(
+        TEST_USER2,
+        owner_of_query(deps.as_ref(), "3".to_string()).owner
+    );
+
+    let res = execute(deps.as_mut(), mock_env(), buyer.clone(), buy_msg.clone());
+    assert_eq!(Err(ContractError::NotListed {}), res);
+
+    // Listings lapse when the tile changes hands.
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(TEST_USER2, &[]),
+        list_msg.clone(),
+    )
+    .unwrap();
+    assert_eq!(
+        Response::new()
+            .add_attribute("action", "list_tile")
+            .add_attribute("token_id", "3")
+            .add_attribute("seller", TEST_USER2)
+            .add_attribute("price", format!("{:?}", coin(1000, "uusd"))),
+        res
+    );
+    let transfer_msg = ExecuteMsg::TransferNft {
+        recipient: TEST_USER.to_string(),
+        token_id: "3".to_string(),
+    };
+    let _res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(TEST_USER2, &[]),
+        transfer_msg,
+    )
+    .unwrap();
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(TEST_CREATOR, &coins(1000, "uusd")),
+        buy_msg,
+    );
+    assert_eq!(Err(ContractError::NotListed {}), res);
+
+    let _res = execute(deps.as_mut(), mock_env(), seller.clone(), list_msg).unwrap();
+    let cancel_msg = ExecuteMsg::CancelListing { token_id: 3 };
+    let res = execute(deps.as_mut(), mock_env(), buyer, cancel_msg.clone());
+    assert_eq!(Err(ContractError::Unauthorized {}), res);
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        seller.clone(),
+        cancel_msg.clone(),
+    )
+    .unwrap();
+    assert_eq!(
+        Response::new()
+            .add_attribute("action", "cancel_listing")
+            .add_attribute("token_id", "3"),
+        res
+    );
+    let res = execute(deps.as_mut(), mock_env(), seller, cancel_msg);
+    assert_eq!(Err(ContractError::NotListed {}), res);
+}
+
+#[test]
+fn pays_royalties_on_secondary_sales() {
+    let mut deps = mock_dependencies(&[]);
+
+    let royalty = Royalty {
+        recipient: Addr::unchecked(TEST_CREATOR),
+        bps: 1000,
+    };
+    let msg = InstantiateMsg {
+        platform_fee: Some(Decimal::percent(5)),
+        royalty: Some(royalty.clone()),
+        ..instantiate_msg(coin(TEST_MINT_FEE_AMOUNT, "uusd"))
+    };
+    let creator = mock_info(TEST_CREATOR, &[]);
+    let _res = instantiate(deps.as_mut(), mock_env(), creator.clone(), msg).unwrap();
+
+    // Together with the platform fee, the royalty may not exceed the sale price.
+    let update_msg = ExecuteMsg::UpdateConfig {
+        owner: None,
+        mint_fee: None,
+        edit_fee: None,
+        edit_cooldown: None,
+        pricing: None,
+        platform_fee: None,
+        royalty: Some(Royalty {
+            recipient: Addr::unchecked(TEST_CREATOR),
+            bps: 9600,
+        }),
+    };
+    let res = execute(deps.as_mut(), mock_env(), creator, update_msg);
+    assert_eq!(Err(ContractError::InvalidRoyalty {}), res);
+
+    let mint_msg = ExecuteMsg::Mint {
+        token_id: 3,
+        color_map: None,
+        pixels: None,
+        url: None,
+    };
+    let _res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(TEST_USER, &coins(TEST_MINT_FEE_AMOUNT, "uusd")),
+        mint_msg,
+    )
+    .unwrap();
+
+    let check: CheckRoyaltiesResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::CheckRoyalties {}).unwrap())
+            .unwrap();
+    assert_eq!(
+        CheckRoyaltiesResponse {
+            royalty_payments: true
+        },
+        check
+    );
+
+    let info: RoyaltiesInfoResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::RoyaltyInfo {
+                token_id: "3".to_string(),
+                sale_price: Uint128::new(1000),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        RoyaltiesInfoResponse {
+            address: TEST_CREATOR.to_string(),
+            royalty_amount: Uint128::new(100),
+        },
+        info
+    );
+
+    let list_msg = ExecuteMsg::ListTile {
+        token_id: 3,
+        price: coin(1000, "uusd"),
+    };
+    let _res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(TEST_USER, &[]),
+        list_msg,
+    )
+    .unwrap();
+
+    let buy_msg = ExecuteMsg::BuyTile { token_id: 3 };
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(TEST_USER2, &coins(1000, "uusd")),
+        buy_msg,
+    )
+    .unwrap();
+    assert_eq!(
+        Response::new()
+            .add_attribute("action", "buy_tile")
+            .add_attribute("token_id", "3")
+            .add_attribute("seller", TEST_USER)
+            .add_attribute("buyer", TEST_USER2)
+            .add_attribute("price", format!("{:?}", coin(1000, "uusd")))
+            .add_attribute("platform_fee", "50")
+            .add_message(BankMsg::Send {
+                to_address: TEST_USER.to_string(),
+                amount: coins(850, "uusd"),
+            })
+            .add_attribute("royalty", "100")
+            .add_message(BankMsg::Send {
+                to_address: TEST_CREATOR.to_string(),
+                amount: coins(100, "uusd"),
+            }),
+        res
+    );
+}
+
+#[test]
+fn can_lease_drawing_rights() {
+    let mut deps = mock_dependencies(&[]);
+
+    let msg = instantiate_msg(coin(TEST_MINT_FEE_AMOUNT, "uusd"));
+    let _res = instantiate(deps.as_mut(), mock_env(), mock_info(TEST_CREATOR, &[]), msg).unwrap();
+
+    let owner = mock_info(TEST_USER, &[]);
+    let mint_msg = ExecuteMsg::Mint {
+        token_id: 4,
+        color_map: None,
+        pixels: None,
+        url: None,
+    };
+    let _res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(TEST_USER, &coins(TEST_MINT_FEE_AMOUNT, "uusd")),
+        mint_msg,
+    )
+    .unwrap();
+
+    let offer_msg = ExecuteMsg::OfferLease {
+        token_id: 4,
+        price: coin(500, "uusd"),
+        duration: Duration::Height(10),
+    };
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(TEST_USER2, &[]),
+        offer_msg.clone(),
+    );
+    assert_eq!(Err(ContractError::Unauthorized {}), res);
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(TEST_USER, &[]),
+        ExecuteMsg::OfferLease {
+            token_id: 5,
+            price: coin(500, "uusd"),
+            duration: Duration::Height(10),
+        },
+    );
+    assert_eq!(Err(ContractError::DoesNotExist {}), res);
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        owner.clone(),
+        ExecuteMsg::OfferLease {
+            token_id: 4,
+            price: coin(500, "uusd"),
+            duration: Duration::Time(0),
+        },
+    );
+    assert_eq!(Err(ContractError::InvalidLease {}), res);
+
+    // Leases ending past the largest block height or time are rejected.
+    for &duration in &[Duration::Height(u64::MAX), Duration::Time(20_000_000_000)] {
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            owner.clone(),
+            ExecuteMsg::OfferLease {
+                token_id: 4,
+                price: coin(500, "uusd"),
+                duration,
+            },
+        );
+        assert_eq!(Err(ContractError::InvalidLease {}), res);
+    }
+
+    let res = execute(deps.as_mut(), mock_env(), owner.clone(), offer_msg.clone()).unwrap();
+    assert_eq!(
+        Response::new()
+            .add_attribute("action", "offer_lease")
+            .add_attribute("token_id", "4")
+            .add_attribute("lessor", TEST_USER)
+            .add_attribute("price", format!("{:?}", coin(500, "uusd")))
+            .add_attribute("duration", Duration::Height(10).to_string()),
+        res
+    );
+
+    // Only the owner can withdraw the offer, after which it can not be accepted.
+    let accept_msg = ExecuteMsg::AcceptLease { token_id: 4 };
+    let lessee = mock_info(TEST_USER2, &coins(500, "uusd"));
+    let cancel_msg = ExecuteMsg::CancelLeaseOffer { token_id: 4 };
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(TEST_USER2, &[]),
+        cancel_msg.clone(),
+    );
+    assert_eq!(Err(ContractError::Unauthorized {}), res);
+    let res = execute(deps.as_mut(), mock_env(), owner.clone(), cancel_msg.clone()).unwrap();
+    assert_eq!(
+        Response::new()
+            .add_attribute("action", "cancel_lease_offer")
+            .add_attribute("token_id", "4"),
+        res
+    );
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        lessee.clone(),
+        accept_msg.clone(),
+    );
+    assert_eq!(Err(ContractError::NoLeaseOffer {}), res);
+    let res = execute(deps.as_mut(), mock_env(), owner.clone(), cancel_msg);
+    assert_eq!(Err(ContractError::NoLeaseOffer {}), res);
+
+    let _res = execute(deps.as_mut(), mock_env(), owner.clone(), offer_msg).unwrap();
+    let expires = Expiration::AtHeight(mock_env().block.height + 10);
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        lessee.clone(),
+        accept_msg.clone(),
+    )
+    .unwrap();
+    assert_eq!(
+        Response::new()
+            .add_attribute("action", "accept_lease")
+            .add_attribute("token_id", "4")
+            .add_attribute("lessor", TEST_USER)
+            .add_attribute("lessee", TEST_USER2)
+            .add_attribute("price", format!("{:?}", coin(500, "uusd")))
+            .add_attribute("expires", expires.to_string())
+            .add_message(BankMsg::Send {
+                to_address: TEST_USER.to_string(),
+                amount: coins(500, "uusd"),
+            }),
+        res
+    );
+
+    let lease: LeaseResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Lease { token_id: 4 }).unwrap())
+            .unwrap();
+    assert_eq!(
+        LeaseResponse {
+            offer: None,
+            lease: Some(Lease {
+                lessee: Addr::unchecked(TEST_USER2),
+                expires,
+            }),
+        },
+        lease
+    );
+
+    let res = execute(deps.as_mut(), mock_env(), lessee, accept_msg);
+    assert_eq!(Err(ContractError::NoLeaseOffer {}), res);
+
+    // Only the lessee may draw while the lease runs, not even the owner.
+    let change_msg = ExecuteMsg::ChangePixelData {
+        token_id: 4,
+        color_map: None,
+        pixels: None,
+        url: Some(TEST_URL.to_string()),
+    };
+    let res = execute(deps.as_mut(), mock_env(), owner.clone(), change_msg.clone());
+    assert_eq!(Err(ContractError::LeaseActive { until: expires }), res);
+
+    let _res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(TEST_USER2, &[]),
+        change_msg.clone(),
+    )
+    .unwrap();
+
+    // Control reverts to the owner once the lease expires.
+    let mut env = mock_env();
+    env.block.height += 10;
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(TEST_USER2, &[]),
+        change_msg.clone(),
+    );
+    assert_eq!(Err(ContractError::Unauthorized {}), res);
+
+    let _res = execute(deps.as_mut(), env, owner, change_msg).unwrap();
+}